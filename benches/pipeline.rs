@@ -0,0 +1,92 @@
+#[path = "../tests/common/mod.rs"]
+mod common;
+
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use common::{compiler, fixture};
+
+const HELPER_COUNT: usize = 400;
+const IMPORT_DEPTH: usize = 30;
+
+fn large_repository() -> Vec<(String, String)> {
+    let mut helpers = String::new();
+    for index in 0..HELPER_COUNT {
+        helpers.push_str(&format!("LIMIT_{} = {}\n\n", index, index * 3));
+        helpers.push_str(&format!("def helper_{}(value):\n    \"\"\"Scale value by helper {}.\"\"\"\n    total = value * LIMIT_{}\n    return total + {}\n\n", index, index, index, index));
+    }
+
+    let imported: Vec<String> = (0..HELPER_COUNT).step_by(2).map(|index| format!("helper_{}", index)).collect();
+    let mut wide = format!("from common.helpers import {}\n", imported.join(", "));
+    wide.extend(imported.iter().map(|name| format!("print({}(1))\n", name)));
+
+    let mut files = vec![
+        (String::from("common/helpers.py"), helpers),
+        (String::from("games/wide/download.py"), wide),
+        (String::from("games/deep/download.py"), String::from("from games.level_0 import run_0\nprint(run_0(1))\n")),
+    ];
+
+    for level in 0..IMPORT_DEPTH {
+        let body = match level + 1 {
+            next if next < IMPORT_DEPTH => format!("from games.level_{} import run_{}\n\ndef run_{}(x):\n    return run_{}(x) + 1\n", next, next, level, next),
+            _ => format!("from common.helpers import helper_1\n\ndef run_{}(x):\n    return helper_1(x)\n", level),
+        };
+        files.push((format!("games/level_{}.py", level), body));
+    }
+
+    files
+}
+
+fn measure(name: &str, root: &Path, iterations: usize, args: &[&str], cache: Option<&Path>) {
+    let run = || {
+        let mut command = compiler(root);
+        if let Some(cache) = cache {
+            command.env("AST_CACHE_DIR", cache);
+        }
+
+        let started = Instant::now();
+        let output = command.args(args).output().unwrap();
+        assert!(output.status.success(), "{}: {}", name, String::from_utf8_lossy(&output.stderr));
+        started.elapsed()
+    };
+
+    run();
+    let mut timings: Vec<Duration> = (0..iterations).map(|_| run()).collect();
+    timings.sort();
+
+    println!(
+        "{:<28} median {:>8.2} ms  min {:>8.2} ms  ({} runs)",
+        name,
+        timings[timings.len() / 2].as_secs_f64() * 1000.0,
+        timings[0].as_secs_f64() * 1000.0,
+        iterations
+    );
+}
+
+fn main() {
+    let iterations = env::var("BENCH_ITERATIONS").ok().and_then(|value| value.parse().ok()).unwrap_or(10);
+
+    let files = large_repository();
+    let borrowed: Vec<(&str, &str)> = files.iter().map(|(path, content)| (path.as_str(), content.as_str())).collect();
+    let root = fixture("bench-pipeline", &borrowed);
+    let cache = root.join("ast-cache");
+    let archive = root.join("bundles.zip");
+    let unpacked = root.join("unpacked");
+
+    println!("{} helpers, import chain {} modules deep", HELPER_COUNT, IMPORT_DEPTH);
+
+    let check = ["check", "-g", "games", "-s", "wide,deep", "-q"];
+    measure("check, no AST cache", &root, iterations, &check, None);
+    measure("check, warm AST cache", &root, iterations, &check, Some(&cache));
+    measure("check --target-os 5.7", &root, iterations, &[&check[..], &["--target-os", "5.7", "--perf-lint"]].concat(), Some(&cache));
+
+    let bundle = ["bundle", "-g", "games", "-s", "wide,deep", "-q", "--output", archive.to_str().unwrap()];
+    measure("bundle to zip", &root, iterations, &bundle, Some(&cache));
+    measure("bundle shaken and minified", &root, iterations, &[&bundle[..], &["--tree-shake", "--minify", "--mangle"]].concat(), Some(&cache));
+
+    let bundle = ["bundle", "-g", "games", "-s", "wide,deep", "-q", "--output", unpacked.to_str().unwrap(), "--unpacked"];
+    measure("bundle unpacked", &root, iterations, &bundle, Some(&cache));
+
+    fs::remove_dir_all(root).unwrap();
+}
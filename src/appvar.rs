@@ -0,0 +1,90 @@
+const SIGNATURE: &[u8] = b"**TI83F*\x1a\x0a\x00";
+const COMMENT: &[u8] = b"Created by ti-84-python-compiler";
+const PYTHON_HEADER: &[u8] = b"PYCD\x00";
+const VARIABLE_HEADER_LENGTH: u16 = 13;
+const APPVAR_TYPE: u8 = 0x15;
+const ARCHIVED: u8 = 0x80;
+
+fn appvar_name(script_name: &str) -> Result<[u8; 8], String> {
+    let is_valid = (1..=8).contains(&script_name.len())
+        && script_name.starts_with(|c: char| c.is_ascii_alphabetic())
+        && script_name.chars().all(|c| c.is_ascii_alphanumeric());
+
+    if !is_valid {
+        return Err(format!(
+            "{} cannot be stored as an AppVar: names must be 1-8 letters or digits starting with a letter",
+            script_name
+        ));
+    }
+
+    let mut name = [0u8; 8];
+    name[..script_name.len()].copy_from_slice(script_name.as_bytes());
+
+    Ok(name)
+}
+
+pub fn create_8xv(script_name: &str, source: &str) -> Result<Vec<u8>, String> {
+    let name = appvar_name(script_name)?;
+
+    let mut payload = PYTHON_HEADER.to_vec();
+    payload.extend_from_slice(source.as_bytes());
+
+    let variable_length = u16::try_from(payload.len() + 2)
+        .ok()
+        .filter(|length| *length as usize + VARIABLE_HEADER_LENGTH as usize + 4 <= u16::MAX as usize)
+        .ok_or_else(|| format!("{}.py is too large for a single AppVar ({} bytes)", script_name, source.len()))?;
+
+    let mut entry = Vec::new();
+    entry.extend_from_slice(&VARIABLE_HEADER_LENGTH.to_le_bytes());
+    entry.extend_from_slice(&variable_length.to_le_bytes());
+    entry.push(APPVAR_TYPE);
+    entry.extend_from_slice(&name);
+    entry.push(0);
+    entry.push(ARCHIVED);
+    entry.extend_from_slice(&variable_length.to_le_bytes());
+    entry.extend_from_slice(&(payload.len() as u16).to_le_bytes());
+    entry.extend_from_slice(&payload);
+
+    let checksum = entry.iter().fold(0u16, |sum, byte| sum.wrapping_add(*byte as u16));
+
+    let mut comment = [0u8; 42];
+    comment[..COMMENT.len()].copy_from_slice(COMMENT);
+
+    let mut file = SIGNATURE.to_vec();
+    file.extend_from_slice(&comment);
+    file.extend_from_slice(&(entry.len() as u16).to_le_bytes());
+    file.extend_from_slice(&entry);
+    file.extend_from_slice(&checksum.to_le_bytes());
+
+    Ok(file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checksum_covers_the_variable_entry() {
+        let file = create_8xv("SNAKE", "print(1)\n").unwrap();
+
+        assert_eq!(&file[..11], SIGNATURE);
+        assert_eq!(&file[11..11 + COMMENT.len()], COMMENT);
+
+        let entry_length = u16::from_le_bytes([file[53], file[54]]) as usize;
+        assert_eq!(file.len(), 55 + entry_length + 2);
+
+        let entry = &file[55..55 + entry_length];
+        assert_eq!(entry[4], APPVAR_TYPE);
+        assert_eq!(&entry[5..13], b"SNAKE\0\0\0");
+        assert_eq!(&entry[19..], b"PYCD\0print(1)\n");
+
+        let expected = entry.iter().fold(0u16, |sum, byte| sum.wrapping_add(*byte as u16));
+        assert_eq!(u16::from_le_bytes([file[file.len() - 2], file[file.len() - 1]]), expected);
+    }
+
+    #[test]
+    fn rejects_names_the_calculator_cannot_store() {
+        assert!(create_8xv("snake_game", "").is_err());
+        assert!(create_8xv("1snake", "").is_err());
+    }
+}
@@ -0,0 +1,171 @@
+use std::collections::HashMap;
+use zip::DateTime;
+
+const CENTRAL_HEADER_SIGNATURE: u32 = 0x02014b50;
+const END_OF_CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x06054b50;
+const ZIP64_LOCATOR_SIGNATURE: u32 = 0x07064b50;
+const CENTRAL_HEADER_LENGTH: usize = 46;
+
+fn read_u16(bytes: &[u8], offset: usize) -> usize {
+    u16::from_le_bytes([bytes[offset], bytes[offset + 1]]) as usize
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap())
+}
+
+fn civil_from_unix(seconds: u64) -> (i64, u8, u8, u8, u8, u8) {
+    let days = (seconds / 86_400) as i64;
+    let second_of_day = seconds % 86_400;
+
+    let shifted = days + 719_468;
+    let era = shifted.div_euclid(146_097);
+    let day_of_era = shifted - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    (
+        year,
+        month as u8,
+        day as u8,
+        (second_of_day / 3_600) as u8,
+        (second_of_day / 60 % 60) as u8,
+        (second_of_day % 60) as u8,
+    )
+}
+
+pub fn zip_datetime(unix_seconds: u64) -> Result<DateTime, String> {
+    let (year, month, day, hour, minute, second) = civil_from_unix(unix_seconds);
+
+    u16::try_from(year)
+        .ok()
+        .and_then(|year| DateTime::from_date_and_time(year, month, day, hour, minute, second).ok())
+        .ok_or_else(|| format!("Modification time {} is outside the 1980-2107 range zip timestamps can store", unix_seconds))
+}
+
+pub fn write_entry_comments(zip_content: Vec<u8>, comments: &HashMap<String, String>) -> Result<Vec<u8>, String> {
+    let corrupt = || String::from("Cannot add entry comments: the archive has no readable central directory");
+
+    let end_record = (0..=zip_content.len().saturating_sub(22))
+        .rev()
+        .find(|offset| read_u32(&zip_content, *offset) == END_OF_CENTRAL_DIRECTORY_SIGNATURE)
+        .ok_or_else(corrupt)?;
+
+    let zip64_locator = end_record.checked_sub(20).filter(|offset| read_u32(&zip_content, *offset) == ZIP64_LOCATOR_SIGNATURE);
+    let zip64_record = zip64_locator.map(|locator| read_u64(&zip_content, locator + 8) as usize);
+
+    let (central_start, central_size) = match zip64_record {
+        Some(record) => (read_u64(&zip_content, record + 48) as usize, read_u64(&zip_content, record + 40) as usize),
+        None => (read_u32(&zip_content, end_record + 16) as usize, read_u32(&zip_content, end_record + 12) as usize),
+    };
+    let central_end = central_start + central_size;
+
+    if central_end > end_record {
+        return Err(corrupt());
+    }
+
+    let mut central_directory = Vec::new();
+    let mut position = central_start;
+
+    while position < central_end {
+        if position + CENTRAL_HEADER_LENGTH > central_end || read_u32(&zip_content, position) != CENTRAL_HEADER_SIGNATURE {
+            return Err(corrupt());
+        }
+
+        let name_length = read_u16(&zip_content, position + 28);
+        let extra_length = read_u16(&zip_content, position + 30);
+        let comment_length = read_u16(&zip_content, position + 32);
+        let header_end = position + CENTRAL_HEADER_LENGTH + name_length + extra_length;
+
+        if header_end + comment_length > central_end {
+            return Err(corrupt());
+        }
+
+        let name = String::from_utf8_lossy(&zip_content[position + CENTRAL_HEADER_LENGTH..position + CENTRAL_HEADER_LENGTH + name_length]);
+        let comment = match comments.get(name.as_ref()) {
+            Some(comment) => comment.as_bytes(),
+            None => &zip_content[header_end..header_end + comment_length],
+        };
+
+        let Ok(new_comment_length) = u16::try_from(comment.len()) else {
+            return Err(format!("Comment for {} is longer than the {} bytes a zip entry can hold", name, u16::MAX));
+        };
+
+        central_directory.extend_from_slice(&zip_content[position..position + 32]);
+        central_directory.extend_from_slice(&new_comment_length.to_le_bytes());
+        central_directory.extend_from_slice(&zip_content[position + 34..header_end]);
+        central_directory.extend_from_slice(comment);
+
+        position = header_end + comment_length;
+    }
+
+    let mut output = zip_content[..central_start].to_vec();
+    output.extend_from_slice(&central_directory);
+    output.extend_from_slice(&zip_content[central_end..]);
+
+    let shift = |offset: usize| offset + central_directory.len() - central_size;
+
+    if let (Some(locator), Some(record)) = (zip64_locator, zip64_record) {
+        output[shift(record) + 40..shift(record) + 48].copy_from_slice(&(central_directory.len() as u64).to_le_bytes());
+        output[shift(locator) + 8..shift(locator) + 16].copy_from_slice(&(shift(record) as u64).to_le_bytes());
+    }
+
+    if read_u32(&zip_content, end_record + 12) != u32::MAX {
+        let central_size = u32::try_from(central_directory.len()).map_err(|_| corrupt())?;
+        output[shift(end_record) + 12..shift(end_record) + 16].copy_from_slice(&central_size.to_le_bytes());
+    }
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Cursor, Read, Write};
+    use zip::write::{FileOptions, ZipWriter};
+
+    fn archive(entries: &[(&str, &str)]) -> Vec<u8> {
+        let mut buffer = Cursor::new(Vec::new());
+        let mut zip = ZipWriter::new(&mut buffer);
+
+        for (name, content) in entries {
+            zip.start_file(*name, FileOptions::<()>::default()).unwrap();
+            zip.write_all(content.as_bytes()).unwrap();
+        }
+
+        zip.finish().unwrap();
+        buffer.into_inner()
+    }
+
+    #[test]
+    fn entry_comments_are_written_to_the_central_directory() {
+        let zip_content = archive(&[("a.py", "print('a')\n"), ("b.py", "print('b')\n")]);
+        let comments = HashMap::from([(String::from("b.py"), String::from("built from b"))]);
+
+        let commented = write_entry_comments(zip_content, &comments).unwrap();
+        let mut reader = zip::ZipArchive::new(Cursor::new(commented)).unwrap();
+
+        assert_eq!(reader.by_name("a.py").unwrap().comment(), "");
+        assert_eq!(reader.by_name("b.py").unwrap().comment(), "built from b");
+
+        let mut content = String::new();
+        reader.by_name("b.py").unwrap().read_to_string(&mut content).unwrap();
+        assert_eq!(content, "print('b')\n");
+    }
+
+    #[test]
+    fn entry_comments_reject_a_truncated_archive() {
+        let zip_content = archive(&[("a.py", "")]);
+        let truncated = zip_content[..zip_content.len() - 10].to_vec();
+
+        assert!(write_entry_comments(truncated, &HashMap::new()).is_err());
+    }
+}
@@ -0,0 +1,213 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, OnceLock};
+use sha2::{Digest, Sha256};
+use crate::python::{Effect, FunctionNames, ImportedName, Statement, StatementKind};
+
+const FORMAT_HEADER: &str = "ti-84-python-compiler statements v2";
+
+fn memory() -> &'static Mutex<HashMap<String, Vec<Statement>>> {
+    static MEMORY: OnceLock<Mutex<HashMap<String, Vec<Statement>>>> = OnceLock::new();
+    MEMORY.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn cache_path(hash: &str) -> Option<PathBuf> {
+    env::var("AST_CACHE_DIR").ok().filter(|dir| !dir.is_empty()).map(|dir| PathBuf::from(dir).join(hash))
+}
+
+pub fn write_atomically(path: &Path, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    static NEXT_TEMPORARY: AtomicUsize = AtomicUsize::new(0);
+    let temporary = path.with_extension(format!("{}-{}.tmp", std::process::id(), NEXT_TEMPORARY.fetch_add(1, Ordering::Relaxed)));
+
+    fs::write(&temporary, contents).and_then(|_| fs::rename(&temporary, path)).inspect_err(|_| {
+        let _ = fs::remove_file(&temporary);
+    })
+}
+
+pub fn content_hash(source: impl AsRef<[u8]>) -> String {
+    Sha256::digest(source).iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+pub fn seal(contents: impl AsRef<[u8]>) -> Vec<u8> {
+    let contents = contents.as_ref();
+    let mut entry = format!("{}\n", content_hash(contents)).into_bytes();
+    entry.extend_from_slice(contents);
+    entry
+}
+
+pub fn unseal(entry: &[u8]) -> Option<&[u8]> {
+    let newline = entry.iter().position(|byte| *byte == b'\n')?;
+    let (hash, contents) = (&entry[..newline], &entry[newline + 1..]);
+
+    (hash == content_hash(contents).as_bytes()).then_some(contents)
+}
+
+fn encode_names(names: &[ImportedName]) -> String {
+    names
+        .iter()
+        .map(|imported| match &imported.alias {
+            Some(alias) => format!("{}={}", imported.name, alias),
+            None => imported.name.clone(),
+        })
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+fn decode_names(field: &str) -> Vec<ImportedName> {
+    field
+        .split(',')
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| match entry.split_once('=') {
+            Some((name, alias)) => ImportedName { name: name.to_string(), alias: Some(alias.to_string()) },
+            None => ImportedName { name: entry.to_string(), alias: None },
+        })
+        .collect()
+}
+
+fn decode_list(field: &str) -> Vec<String> {
+    field.split(',').filter(|name| !name.is_empty()).map(|name| name.to_string()).collect()
+}
+
+fn encode_function(function: &Option<FunctionNames>) -> String {
+    match function {
+        Some(function) => format!("{};{}", function.body.join(","), function.globals.join(",")),
+        None => String::from("-"),
+    }
+}
+
+fn decode_function(field: &str) -> Option<Option<FunctionNames>> {
+    if field == "-" {
+        return Some(None);
+    }
+
+    let (body, globals) = field.split_once(';')?;
+    Some(Some(FunctionNames { body: decode_list(body), globals: decode_list(globals) }))
+}
+
+fn serialize(statements: &[Statement]) -> String {
+    let mut lines = vec![FORMAT_HEADER.to_string()];
+
+    for statement in statements {
+        let kind = match &statement.kind {
+            StatementKind::Definition(name) => format!("D\t{}", name),
+            StatementKind::Assignment(names) => format!("A\t{}", names.join(",")),
+            StatementKind::Import(names) => format!("I\t{}", encode_names(names)),
+            StatementKind::ImportFrom { module, level, names } => format!("F\t{}\t{}\t{}", level, module, encode_names(names)),
+            StatementKind::Other => String::from("O"),
+        };
+
+        let effect = match statement.effect {
+            Effect::None => "N",
+            Effect::Runs => "R",
+            Effect::MainGuard => "M",
+        };
+
+        lines.push(format!(
+            "{}\t{}\t{}\t{}\t{}\t{}",
+            statement.first_line,
+            statement.last_line,
+            effect,
+            statement.references.join(","),
+            encode_function(&statement.function),
+            kind
+        ));
+    }
+
+    lines.join("\n")
+}
+
+fn deserialize(content: &str) -> Option<Vec<Statement>> {
+    let mut lines = content.lines();
+
+    if lines.next()? != FORMAT_HEADER {
+        return None;
+    }
+
+    lines
+        .map(|line| {
+            let fields: Vec<&str> = line.split('\t').collect();
+
+            let kind = match *fields.get(5)? {
+                "D" => StatementKind::Definition(fields.get(6)?.to_string()),
+                "A" => StatementKind::Assignment(decode_list(fields.get(6)?)),
+                "I" => StatementKind::Import(decode_names(fields.get(6)?)),
+                "F" => StatementKind::ImportFrom {
+                    level: fields.get(6)?.parse().ok()?,
+                    module: fields.get(7)?.to_string(),
+                    names: decode_names(fields.get(8)?),
+                },
+                "O" => StatementKind::Other,
+                _ => return None,
+            };
+
+            Some(Statement {
+                first_line: fields.first()?.parse().ok()?,
+                last_line: fields.get(1)?.parse().ok()?,
+                kind,
+                effect: match *fields.get(2)? {
+                    "N" => Effect::None,
+                    "R" => Effect::Runs,
+                    "M" => Effect::MainGuard,
+                    _ => return None,
+                },
+                references: decode_list(fields.get(3)?),
+                function: decode_function(fields.get(4)?)?,
+            })
+        })
+        .collect()
+}
+
+pub fn lookup(hash: &str) -> Option<Vec<Statement>> {
+    if let Some(statements) = memory().lock().ok()?.get(hash) {
+        return Some(statements.clone());
+    }
+
+    let entry = fs::read(cache_path(hash)?).ok()?;
+    let statements = deserialize(std::str::from_utf8(unseal(&entry)?).ok()?)?;
+    memory().lock().ok()?.insert(hash.to_string(), statements.clone());
+
+    Some(statements)
+}
+
+pub fn store(hash: &str, statements: &[Statement]) {
+    if let Ok(mut memory) = memory().lock() {
+        memory.insert(hash.to_string(), statements.to_vec());
+    }
+
+    if let Some(path) = cache_path(hash) {
+        let written = path.parent().map_or(Ok(()), fs::create_dir_all).and_then(|_| write_atomically(&path, seal(serialize(statements))));
+
+        if let Err(err) = written {
+            eprintln!("warning: could not write AST cache entry {}: {}", path.display(), err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::python::{parse_statements, Effect};
+
+    #[test]
+    fn cached_statements_keep_effects_and_names() {
+        let lines: Vec<String> = "import ti_draw as d\nSPEED = 2\ndef step(x):\n    global SPEED\n    return d.pos(x) + SPEED\nsetup()\nif __name__ == \"__main__\":\n    step(1)"
+            .lines()
+            .map(|line| line.to_string())
+            .collect();
+        let parsed = parse_statements(&lines, "<test>").unwrap();
+        let restored = deserialize(&serialize(&parsed)).expect("entry should round-trip");
+
+        assert_eq!(serialize(&restored), serialize(&parsed));
+
+        let function = parsed[2].function.as_ref().expect("step() should record its names");
+        assert_eq!(function.body, ["SPEED", "d", "x"]);
+        assert_eq!(function.globals, ["SPEED"]);
+        assert_eq!(parsed[2].references, ["step", "x", "SPEED", "d"]);
+        assert_eq!(parsed.iter().map(|statement| statement.effect == Effect::None).collect::<Vec<bool>>(), [true, true, true, false, false]);
+        assert!(parsed[4].effect == Effect::MainGuard);
+    }
+}
@@ -0,0 +1,44 @@
+use crate::python;
+
+const PYTHON_BUILTINS: &[&str] = &[
+    "abs", "all", "any", "bin", "bool", "bytearray", "bytes", "callable", "chr", "classmethod", "compile",
+    "complex", "delattr", "dict", "dir", "divmod", "enumerate", "eval", "exec", "filter", "float", "format",
+    "frozenset", "getattr", "globals", "hasattr", "hash", "help", "hex", "id", "input", "int", "isinstance",
+    "issubclass", "iter", "len", "list", "locals", "map", "max", "min", "next", "object", "oct", "open", "ord",
+    "pow", "print", "property", "range", "repr", "reversed", "round", "set", "setattr", "slice", "sorted",
+    "staticmethod", "str", "sum", "super", "tuple", "type", "vars", "zip",
+];
+
+const MICROPYTHON_BUILTINS: &[&str] = &["const", "micropython"];
+
+pub struct Shadowing {
+    pub index: usize,
+    pub name: String,
+    pub builtin_kind: &'static str,
+}
+
+pub fn find_shadowed_builtins(lines: &[String]) -> Vec<Shadowing> {
+    let statements = python::parse_statements(lines, "<module>").unwrap_or_default();
+
+    let mut shadowings = Vec::new();
+
+    for statement in &statements {
+        for name in statement.bound_names() {
+            let builtin_kind = if PYTHON_BUILTINS.contains(&name) {
+                "Python"
+            } else if MICROPYTHON_BUILTINS.contains(&name) {
+                "MicroPython"
+            } else {
+                continue;
+            };
+
+            shadowings.push(Shadowing {
+                index: statement.first_line,
+                name: name.to_string(),
+                builtin_kind,
+            });
+        }
+    }
+
+    shadowings
+}
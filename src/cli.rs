@@ -0,0 +1,330 @@
+use std::path::PathBuf;
+use clap::{Args, Parser, Subcommand};
+use crate::sizes::{self, Growth};
+
+#[derive(Parser)]
+#[command(version, about = "Bundle TI-84 Plus CE Python projects into single-file calculator scripts")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Command,
+
+    /// Ignore cached directory listings younger than LISTING_CACHE_TTL and ask the server again
+    #[arg(long, global = true)]
+    pub refresh: bool,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Bundle scripts and emit them through an output sink
+    Bundle(BundleArgs),
+    /// Resolve and lint scripts without emitting anything
+    Check(CheckArgs),
+    /// Bundle every script in every group and compare the results with the files under snapshots/
+    Snapshot(SnapshotArgs),
+    /// Resolve every script and report the ones that import names common/helpers.py no longer defines
+    CheckHelpers {
+        /// Fail when a chain of project imports nests deeper than this
+        #[arg(long, value_name = "N", env = "MAX_IMPORT_DEPTH", default_value = "32")]
+        max_import_depth: usize,
+    },
+    /// List every script that imports a helper, with the file and line of each import
+    WhoUses {
+        symbol: String,
+
+        /// Dotted module the symbol is defined in
+        #[arg(long, default_value = "common.helpers")]
+        module: String,
+
+        /// Fail when a chain of project imports nests deeper than this
+        #[arg(long, value_name = "N", env = "MAX_IMPORT_DEPTH", default_value = "32")]
+        max_import_depth: usize,
+    },
+    /// List the functions and constants in common/helpers.py that no script uses, directly or through another helper
+    DeadHelpers {
+        /// Fail when a chain of project imports nests deeper than this
+        #[arg(long, value_name = "N", env = "MAX_IMPORT_DEPTH", default_value = "32")]
+        max_import_depth: usize,
+    },
+    /// List the groups under ROOT_DIRECTORY, or the scripts in a group
+    List {
+        group: Option<String>,
+    },
+    /// Build a class handout for a group: every script, a menu launcher, .py and .8xv files and a README
+    Pack(PackArgs),
+    /// Write scripts as .8xv files to a folder and open TI Connect CE with them queued
+    Transfer(TransferArgs),
+    /// Print one helper symbol and its dependencies as standalone Python
+    Extract {
+        /// Dotted module path, e.g. common.helpers
+        module: String,
+        symbol: String,
+    },
+}
+
+#[derive(Args)]
+pub struct ScriptSelection {
+    /// Group directory containing the scripts
+    #[arg(short, long)]
+    pub group: String,
+
+    /// Script names, repeated or comma-separated; bundles are always emitted in alphabetical order
+    #[arg(short, long = "script", value_delimiter = ',', required_unless_present = "stdin")]
+    pub scripts: Vec<String>,
+
+    /// Read the entry script from stdin instead of download.py
+    #[arg(long, conflicts_with = "scripts")]
+    pub stdin: bool,
+
+    /// Output name for --stdin, also used to resolve `from .script` imports
+    #[arg(long, default_value = "stdin")]
+    pub name: String,
+}
+
+#[derive(Args)]
+pub struct BuildOptions {
+    /// Fail on rule violations from the named rule set
+    #[arg(long, value_parser = ["exam"])]
+    pub ruleset: Option<String>,
+
+    /// Fail on Python features the calculator's OS version does not support
+    #[arg(long, value_name = "OS_VERSION", value_parser = ["5.5", "5.6", "5.7", "5.8"])]
+    pub target_os: Option<String>,
+
+    /// With --target-os, break long literal print() messages at word boundaries to fit the calculator screen
+    #[arg(long, requires = "target_os")]
+    pub wrap_text: bool,
+
+    /// With --target-os, route runs of print() calls longer than the screen through an injected paged_print() helper
+    #[arg(long, requires = "target_os")]
+    pub paginate: bool,
+
+    /// Warn about patterns that are slow on the calculator, such as string building and allocations inside loops
+    #[arg(long)]
+    pub perf_lint: bool,
+
+    /// Stub desktop-only imports or strip DEV-guarded branches
+    #[arg(long, value_parser = ["stub", "strip"])]
+    pub desktop_modules: Option<String>,
+
+    /// Prepend stand-ins for ti_system, ti_plotlib and ti_draw so the bundle runs on desktop Python for smoke tests (not for .8xv, pack or transfer)
+    #[arg(long)]
+    pub with_shims: bool,
+
+    /// Drop top-level functions, classes and constants that nothing in the bundle references
+    #[arg(long)]
+    pub tree_shake: bool,
+
+    /// Strip comments, docstrings and blank lines from each bundle to save calculator memory
+    #[arg(long)]
+    pub minify: bool,
+
+    /// Shorten local variable and private (_underscore) helper names; public names are kept
+    #[arg(long)]
+    pub mangle: bool,
+
+    /// Print the bytes each inlined function and constant contributes to stderr
+    #[arg(long, value_name = "FORMAT", value_parser = ["text", "json"], num_args = 0..=1, default_missing_value = "text")]
+    pub size_report: Option<String>,
+
+    /// Warn when a bundle is larger than this many bytes (the Python App has roughly 150 KB of heap)
+    #[arg(long, value_name = "BYTES", env = "MEMORY_LIMIT", default_value = "153600")]
+    pub memory_limit: usize,
+
+    /// Warn when a ti_draw or ti_plotlib game loop makes more drawing calls than this per frame
+    #[arg(long, value_name = "CALLS", env = "FRAME_BUDGET", default_value = "100")]
+    pub frame_budget: usize,
+
+    /// Fail when a bundle grew by more than this (bytes, or a percentage like 10%) since the last build recorded in SIZE_RECORD
+    #[arg(long, value_name = "BYTES|PERCENT", value_parser = sizes::parse_growth)]
+    pub max_growth: Option<Growth>,
+
+    /// Embed this student or build ID in each bundle as an obfuscated constant and a trailing-whitespace fingerprint
+    #[arg(long, value_name = "ID", env = "WATERMARK_ID")]
+    pub watermark: Option<String>,
+
+    /// Stop at the first script that fails to bundle instead of finishing the batch
+    #[arg(long)]
+    pub fail_fast: bool,
+
+    /// Fetch and bundle this many scripts at once (defaults to the number of CPUs)
+    #[arg(short, long, value_name = "N")]
+    pub jobs: Option<usize>,
+
+    /// Fail when a chain of project imports nests deeper than this
+    #[arg(long, value_name = "N", env = "MAX_IMPORT_DEPTH", default_value = "32")]
+    pub max_import_depth: usize,
+
+    /// Give up on a script once resolving it has taken this many seconds
+    #[arg(long, value_name = "SECONDS")]
+    pub timeout: Option<u64>,
+
+    /// Suppress warnings and previews
+    #[arg(short, long)]
+    pub quiet: bool,
+}
+
+#[derive(Args)]
+pub struct BundleArgs {
+    #[command(flatten)]
+    pub selection: ScriptSelection,
+
+    #[command(flatten)]
+    pub build: BuildOptions,
+
+    /// Output sink used to emit the bundles: zip prints the base64 zip to stdout, clipboard copies it and shows a notification
+    #[arg(long, env = "OUTPUT_SINK", default_value = "zip")]
+    pub sink: String,
+
+    /// File format of each bundle: Python source or a TI-84 Plus CE Python AppVar
+    #[arg(long, value_parser = ["py", "8xv"], default_value = "py")]
+    pub format: String,
+
+    /// Write the zip to this file (or into this directory) instead of using the sink
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+
+    /// With --output, write each bundle as a .py file into the directory instead of zipping
+    #[arg(long, requires = "output")]
+    pub unpacked: bool,
+
+    /// Append a short content hash to each emitted file name, e.g. snake-3fa9c2.py
+    #[arg(long)]
+    pub hash_names: bool,
+
+    /// Unix permissions of each emitted file, in octal
+    #[arg(long, value_name = "OCTAL", default_value = "644", value_parser = parse_mode)]
+    pub entry_mode: u32,
+
+    /// Modification time of each emitted file, in seconds since the Unix epoch (defaults to now)
+    #[arg(long, value_name = "SECONDS", env = "SOURCE_DATE_EPOCH")]
+    pub entry_mtime: Option<u64>,
+
+    /// Comment stored on each zip entry
+    #[arg(long)]
+    pub entry_comment: Option<String>,
+
+    /// Print the module dependency graph of each bundle instead of emitting it
+    #[arg(long, value_name = "FORMAT", value_parser = ["dot", "json"])]
+    pub graph: Option<String>,
+
+    /// Report what would be emitted without calling the sink
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Print each bundle to stderr
+    #[arg(long)]
+    pub dev: bool,
+
+    /// Print each bundle to stderr with the source file and line of every line
+    #[arg(long)]
+    pub annotate: bool,
+
+    /// Keep running and rebuild a script whenever one of its files or common/helpers.py changes
+    #[arg(long, conflicts_with_all = ["stdin", "graph", "fail_fast"])]
+    pub watch: bool,
+}
+
+#[derive(Args)]
+pub struct CheckArgs {
+    /// Group directory containing the scripts
+    #[arg(short, long, required_unless_present = "changed")]
+    pub group: Option<String>,
+
+    /// Script names, repeated or comma-separated
+    #[arg(short, long = "script", value_delimiter = ',', required_unless_present_any = ["stdin", "changed"])]
+    pub scripts: Vec<String>,
+
+    /// Read the entry script from stdin instead of download.py
+    #[arg(long, conflicts_with = "scripts")]
+    pub stdin: bool,
+
+    /// Output name for --stdin, also used to resolve `from .script` imports
+    #[arg(long, default_value = "stdin")]
+    pub name: String,
+
+    /// Check only the scripts that include one of these files, e.g. the paths staged in a pre-commit hook
+    #[arg(long, value_name = "PATH", num_args = 1.., conflicts_with_all = ["group", "scripts", "stdin"])]
+    pub changed: Vec<PathBuf>,
+
+    #[command(flatten)]
+    pub build: BuildOptions,
+}
+
+#[derive(Args)]
+pub struct SnapshotArgs {
+    #[command(flatten)]
+    pub build: BuildOptions,
+
+    /// Rewrite the snapshots from the current bundles instead of comparing
+    #[arg(long)]
+    pub update: bool,
+
+    /// Directory holding one <group>/<script>.py snapshot per script
+    #[arg(long, default_value = "snapshots")]
+    pub directory: PathBuf,
+}
+
+#[derive(Args)]
+pub struct PackArgs {
+    /// Group directory whose scripts go into the pack
+    pub group: String,
+
+    #[command(flatten)]
+    pub build: BuildOptions,
+
+    /// Directory to write the pack into (defaults to <group>-pack)
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+
+    /// Modification time of each emitted file, in seconds since the Unix epoch (defaults to now)
+    #[arg(long, value_name = "SECONDS", env = "SOURCE_DATE_EPOCH")]
+    pub entry_mtime: Option<u64>,
+}
+
+#[derive(Args)]
+pub struct TransferArgs {
+    #[command(flatten)]
+    pub selection: ScriptSelection,
+
+    #[command(flatten)]
+    pub build: BuildOptions,
+
+    /// Folder to write the .8xv files into (defaults to a folder under the system temp directory)
+    #[arg(long)]
+    pub folder: Option<PathBuf>,
+
+    /// Keep running and send each script to TI Connect CE again whenever one of its files or common/helpers.py changes
+    #[arg(long, conflicts_with_all = ["stdin", "fail_fast"])]
+    pub watch: bool,
+}
+
+impl CheckArgs {
+    pub fn selection(&self) -> Option<ScriptSelection> {
+        Some(ScriptSelection {
+            group: self.group.clone()?,
+            scripts: self.scripts.clone(),
+            stdin: self.stdin,
+            name: self.name.clone(),
+        })
+    }
+}
+
+impl Command {
+    pub fn build_options(&self) -> Option<&BuildOptions> {
+        match self {
+            Command::Bundle(args) => Some(&args.build),
+            Command::Check(args) => Some(&args.build),
+            Command::Snapshot(args) => Some(&args.build),
+            Command::Pack(args) => Some(&args.build),
+            Command::Transfer(args) => Some(&args.build),
+            Command::CheckHelpers { .. } | Command::WhoUses { .. } | Command::DeadHelpers { .. } | Command::List { .. } | Command::Extract { .. } => None,
+        }
+    }
+}
+
+fn parse_mode(value: &str) -> Result<u32, String> {
+    u32::from_str_radix(value.trim_start_matches("0o"), 8)
+        .ok()
+        .filter(|mode| *mode <= 0o7777)
+        .ok_or_else(|| format!("{} is not an octal permission mode such as 644", value))
+}
@@ -0,0 +1,94 @@
+use std::env;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use crate::tools::{self, MissingTool};
+
+fn copy_commands() -> Vec<Vec<String>> {
+    if let Ok(command) = env::var("CLIPBOARD_COMMAND") {
+        return vec![command.split_whitespace().map(|part| part.to_string()).collect()];
+    }
+
+    let commands: &[&[&str]] = if cfg!(target_os = "macos") {
+        &[&["pbcopy"]]
+    } else if cfg!(target_os = "windows") {
+        &[&["clip"]]
+    } else {
+        &[&["wl-copy"], &["xclip", "-selection", "clipboard"], &["xsel", "--clipboard", "--input"]]
+    };
+
+    commands.iter().map(|command| command.iter().map(|part| part.to_string()).collect()).collect()
+}
+
+fn pipe_to(command: &[String], text: &str) -> Option<Result<(), String>> {
+    let (program, args) = command.split_first()?;
+    let mut child = Command::new(program).args(args).stdin(Stdio::piped()).stdout(Stdio::null()).stderr(Stdio::null()).spawn().ok()?;
+
+    let written = child.stdin.take().map_or(Ok(()), |mut stdin| stdin.write_all(text.as_bytes()));
+    let status = child.wait();
+
+    Some(match (written, status) {
+        (Ok(()), Ok(status)) if status.success() => Ok(()),
+        (Err(err), _) | (_, Err(err)) => Err(format!("Failed to copy with {}: {}", program, err)),
+        (_, Ok(status)) => Err(format!("Failed to copy with {}: {}", program, status)),
+    })
+}
+
+pub fn available() -> bool {
+    copy_commands().iter().any(|command| command.first().is_some_and(|program| tools::on_path(program)))
+}
+
+pub fn missing_tools() -> Vec<MissingTool> {
+    let mut missing = Vec::new();
+
+    if !available() {
+        let commands = copy_commands();
+        let tried: Vec<&str> = commands.iter().filter_map(|command| command.first().map(|program| program.as_str())).collect();
+
+        missing.push(MissingTool {
+            name: tried.join(", "),
+            skipped: String::from("the clipboard sink will print the base64 zip to stdout instead"),
+            hint: String::from(if cfg!(any(target_os = "macos", target_os = "windows")) {
+                "set CLIPBOARD_COMMAND to a program that reads stdin"
+            } else {
+                "install wl-clipboard, xclip or xsel, or set CLIPBOARD_COMMAND to a program that reads stdin"
+            }),
+        });
+    }
+
+    if cfg!(all(unix, not(target_os = "macos"))) && !tools::on_path("notify-send") {
+        missing.push(MissingTool {
+            name: String::from("notify-send"),
+            skipped: String::from("no desktop notification will be shown"),
+            hint: String::from("install libnotify (libnotify-bin on Debian and Ubuntu)"),
+        });
+    }
+
+    missing
+}
+
+pub fn copy(text: &str) -> Result<(), String> {
+    let commands = copy_commands();
+
+    commands.iter().find_map(|command| pipe_to(command, text)).unwrap_or_else(|| {
+        let tried: Vec<&str> = commands.iter().filter_map(|command| command.first().map(|program| program.as_str())).collect();
+        Err(format!("No clipboard tool found (tried {}); set CLIPBOARD_COMMAND to one that reads stdin", tried.join(", ")))
+    })
+}
+
+pub fn notify(title: &str, message: &str) {
+    let mut command = if cfg!(target_os = "macos") {
+        let mut command = Command::new("osascript");
+        command.arg("-e").arg(format!("display notification {:?} with title {:?}", message, title));
+        command
+    } else if cfg!(target_os = "windows") {
+        return;
+    } else if tools::on_path("notify-send") {
+        let mut command = Command::new("notify-send");
+        command.arg(title).arg(message);
+        command
+    } else {
+        return;
+    };
+
+    let _ = command.stdout(Stdio::null()).stderr(Stdio::null()).status();
+}
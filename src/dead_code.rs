@@ -0,0 +1,220 @@
+use std::collections::HashSet;
+use rustpython_parser::ast::{self, Ranged};
+use rustpython_parser::Tok;
+use crate::python;
+
+const DYNAMIC_LOOKUPS: &[&str] = &["globals", "vars", "eval", "exec"];
+
+pub struct Elimination {
+    pub lines: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+struct TopLevel {
+    first_line: usize,
+    last_line: usize,
+    bound: Vec<String>,
+    references: HashSet<String>,
+    droppable: bool,
+}
+
+fn is_pure(expr: &ast::Expr) -> bool {
+    match expr {
+        ast::Expr::Constant(_) | ast::Expr::Name(_) => true,
+        ast::Expr::Tuple(expr) => expr.elts.iter().all(is_pure),
+        ast::Expr::List(expr) => expr.elts.iter().all(is_pure),
+        ast::Expr::Set(expr) => expr.elts.iter().all(is_pure),
+        ast::Expr::Dict(expr) => expr.keys.iter().flatten().chain(&expr.values).all(is_pure),
+        ast::Expr::UnaryOp(expr) => is_pure(&expr.operand),
+        ast::Expr::BinOp(expr) => is_pure(&expr.left) && is_pure(&expr.right),
+        ast::Expr::BoolOp(expr) => expr.values.iter().all(is_pure),
+        ast::Expr::Compare(expr) => is_pure(&expr.left) && expr.comparators.iter().all(is_pure),
+        ast::Expr::IfExp(expr) => is_pure(&expr.test) && is_pure(&expr.body) && is_pure(&expr.orelse),
+        _ => false,
+    }
+}
+
+fn simple_targets(targets: &[ast::Expr]) -> Option<Vec<String>> {
+    targets
+        .iter()
+        .map(|target| match target {
+            ast::Expr::Name(name) => Some(name.id.to_string()),
+            _ => None,
+        })
+        .collect()
+}
+
+fn droppable_bindings(statement: &ast::Stmt) -> Option<Vec<String>> {
+    match statement {
+        ast::Stmt::FunctionDef(def) if def.decorator_list.is_empty() => Some(vec![def.name.to_string()]),
+        ast::Stmt::AsyncFunctionDef(def) if def.decorator_list.is_empty() => Some(vec![def.name.to_string()]),
+        ast::Stmt::ClassDef(def) if def.decorator_list.is_empty() => Some(vec![def.name.to_string()]),
+        ast::Stmt::Assign(assign) if is_pure(&assign.value) => simple_targets(&assign.targets),
+        ast::Stmt::AnnAssign(assign) if assign.value.as_deref().is_some_and(is_pure) => {
+            simple_targets(std::slice::from_ref(&*assign.target))
+        }
+        _ => None,
+    }
+}
+
+pub fn eliminate_dead_code(lines: Vec<String>) -> Elimination {
+    let unchanged = |lines| Elimination { lines, removed: Vec::new() };
+
+    let parsed = python::parse_source(&lines);
+
+    let Ok(suite) = &parsed.suite else {
+        return unchanged(lines);
+    };
+
+    let names: Vec<(String, usize)> = parsed
+        .tokens
+        .iter()
+        .filter_map(|(token, start, _)| match token {
+            Tok::Name { name } => Some((name.clone(), *start)),
+            _ => None,
+        })
+        .collect();
+
+    if names.iter().any(|(name, _)| DYNAMIC_LOOKUPS.contains(&name.as_str())) {
+        return unchanged(lines);
+    }
+
+    let mut statements: Vec<TopLevel> = suite
+        .iter()
+        .map(|statement| {
+            let start = usize::from(statement.start());
+            let end = usize::from(statement.end());
+            let bindings = droppable_bindings(statement);
+            let own_names = bindings.clone().unwrap_or_default();
+
+            TopLevel {
+                first_line: parsed.line_index(start),
+                last_line: parsed.line_index(end.saturating_sub(1)),
+                references: names
+                    .iter()
+                    .filter(|(name, offset)| (start..end).contains(offset) && !own_names.contains(name))
+                    .map(|(name, _)| name.clone())
+                    .collect(),
+                droppable: bindings.is_some(),
+                bound: own_names,
+            }
+        })
+        .collect();
+
+    for index in 0..statements.len() {
+        let shares_line = (index > 0 && statements[index - 1].last_line == statements[index].first_line)
+            || statements.get(index + 1).is_some_and(|next| next.first_line == statements[index].last_line);
+
+        if shares_line {
+            statements[index].droppable = false;
+        }
+    }
+
+    let mut live: HashSet<String> = HashSet::new();
+    let mut kept: Vec<bool> = statements.iter().map(|statement| !statement.droppable).collect();
+
+    for statement in statements.iter().filter(|statement| !statement.droppable) {
+        live.extend(statement.references.iter().cloned());
+    }
+
+    loop {
+        let mut changed = false;
+
+        for (index, statement) in statements.iter().enumerate() {
+            if !kept[index] && statement.bound.iter().any(|name| live.contains(name)) {
+                kept[index] = true;
+                live.extend(statement.references.iter().cloned());
+                changed = true;
+            }
+        }
+
+        if !changed {
+            break;
+        }
+    }
+
+    let mut removed_lines = vec![false; lines.len()];
+    let mut removed = Vec::new();
+
+    for (statement, _) in statements.iter().zip(&kept).filter(|(_, kept)| !**kept) {
+        let mut last_line = statement.last_line;
+        while last_line + 1 < lines.len() && lines[last_line + 1].trim().is_empty() {
+            last_line += 1;
+        }
+
+        removed_lines[statement.first_line..=last_line].iter_mut().for_each(|line| *line = true);
+        for name in &statement.bound {
+            if !removed.contains(name) {
+                removed.push(name.clone());
+            }
+        }
+    }
+
+    Elimination {
+        lines: lines
+            .into_iter()
+            .zip(removed_lines)
+            .filter(|(_, removed)| !removed)
+            .map(|(line, _)| line)
+            .collect(),
+        removed,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shake(source: &str) -> (String, Vec<String>) {
+        let elimination = eliminate_dead_code(source.lines().map(|line| line.to_string()).collect());
+
+        (elimination.lines.join("\n"), elimination.removed)
+    }
+
+    #[test]
+    fn drops_unreferenced_definitions_and_their_blank_lines() {
+        let (source, removed) = shake("def used():\n    return 1\n\ndef unused():\n    return 2\n\nLIMIT = 3\nprint(used())");
+
+        assert_eq!(source, "def used():\n    return 1\n\nprint(used())");
+        assert_eq!(removed, vec!["unused", "LIMIT"]);
+    }
+
+    #[test]
+    fn keeps_definitions_reached_through_other_definitions() {
+        let (source, removed) = shake("SIZE = 4\ndef area():\n    return SIZE * SIZE\ndef draw():\n    print(area())\ndraw()");
+
+        assert_eq!(source, "SIZE = 4\ndef area():\n    return SIZE * SIZE\ndef draw():\n    print(area())\ndraw()");
+        assert!(removed.is_empty());
+    }
+
+    #[test]
+    fn keeps_assignments_that_may_raise_or_run_code() {
+        let source = "cfg = os.environ[\"X\"]\nx = obj.prop\nscores = load()\nprint(1)";
+
+        assert_eq!(shake(source), (source.to_string(), Vec::new()));
+    }
+
+    #[test]
+    fn keeps_decorated_definitions() {
+        let source = "@register\ndef handler():\n    pass\nprint(1)";
+
+        assert_eq!(shake(source), (source.to_string(), Vec::new()));
+    }
+
+    #[test]
+    fn keeps_statements_that_share_a_line() {
+        let (source, removed) = shake("a = 1; b = 2\nc = 3\nprint(0)");
+
+        assert_eq!(source, "a = 1; b = 2\nprint(0)");
+        assert_eq!(removed, vec!["c"]);
+    }
+
+    #[test]
+    fn dynamic_lookups_disable_shaking() {
+        for lookup in ["globals()[\"helper\"]()", "eval(\"helper()\")", "vars()"] {
+            let source = format!("def helper():\n    pass\n{}", lookup);
+
+            assert_eq!(shake(&source), (source.clone(), Vec::new()));
+        }
+    }
+}
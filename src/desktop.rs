@@ -0,0 +1,215 @@
+use rustpython_parser::ast::{self, Ranged};
+use crate::python::{self, line_index, ParsedSource};
+
+const DESKTOP_ONLY_MODULES: &[&str] = &["matplotlib", "numpy", "pandas", "scipy", "sympy", "tkinter", "pygame", "PIL"];
+
+const STUB_CLASS: &[&str] = &[
+    "class _DesktopStub:",
+    "    def __getattr__(self, name):",
+    "        return self",
+    "    def __call__(self, *args, **kwargs):",
+    "        return self",
+    "",
+];
+
+pub enum DesktopModuleMode {
+    Stub,
+    Strip,
+}
+
+pub fn parse_mode(name: &str) -> Option<DesktopModuleMode> {
+    match name {
+        "stub" => Some(DesktopModuleMode::Stub),
+        "strip" => Some(DesktopModuleMode::Strip),
+        _ => None,
+    }
+}
+
+pub fn apply_mode(lines: Vec<String>, mode: &DesktopModuleMode) -> Vec<String> {
+    match mode {
+        DesktopModuleMode::Stub => stub_desktop_imports(lines),
+        DesktopModuleMode::Strip => strip_dev_guarded_blocks(lines),
+    }
+}
+
+const GUARD_NAMES: &[&str] = &["DEV", "DEBUG", "DESKTOP"];
+
+struct Rewriter<'a> {
+    source: &'a str,
+    line_starts: &'a [usize],
+    replacements: Vec<(usize, usize, String)>,
+}
+
+impl Rewriter<'_> {
+    fn statement_start(&self, statement: &ast::Stmt) -> usize {
+        let decorators: &[ast::Expr] = match statement {
+            ast::Stmt::FunctionDef(def) => &def.decorator_list,
+            ast::Stmt::AsyncFunctionDef(def) => &def.decorator_list,
+            ast::Stmt::ClassDef(def) => &def.decorator_list,
+            _ => &[],
+        };
+        let start = usize::from(statement.start());
+
+        match decorators.first() {
+            Some(decorator) => self.source[..usize::from(decorator.start())].rfind('@').unwrap_or(start),
+            None => start,
+        }
+    }
+
+    fn line_prefix(&self, offset: usize) -> &str {
+        let line_start = self.line_starts[line_index(self.line_starts, offset)];
+        &self.source[line_start..offset]
+    }
+
+    fn suite_range(&self, suite: &[ast::Stmt]) -> Option<(usize, usize)> {
+        Some((self.statement_start(suite.first()?), usize::from(suite.last()?.end())))
+    }
+
+    fn strip_guards(&mut self, body: &[ast::Stmt]) {
+        for statement in body {
+            let ast::Stmt::If(block) = statement else {
+                for suite in python::child_bodies(statement) {
+                    self.strip_guards(suite);
+                }
+                continue;
+            };
+
+            let negated = match &*block.test {
+                ast::Expr::Name(name) if GUARD_NAMES.contains(&name.id.as_str()) => false,
+                ast::Expr::UnaryOp(unary)
+                    if matches!(unary.op, ast::UnaryOp::Not)
+                        && matches!(&*unary.operand, ast::Expr::Name(name) if GUARD_NAMES.contains(&name.id.as_str())) =>
+                {
+                    true
+                }
+                _ => {
+                    self.strip_guards(&block.body);
+                    self.strip_guards(&block.orelse);
+                    continue;
+                }
+            };
+
+            if !negated {
+                if let Some((start, end)) = self.suite_range(&block.body) {
+                    self.replacements.push((start, end, String::from("pass")));
+                }
+                self.strip_guards(&block.orelse);
+                continue;
+            }
+
+            self.strip_guards(&block.body);
+
+            let Some((start, end)) = self.suite_range(&block.orelse) else {
+                continue;
+            };
+
+            if self.source[start..].starts_with("elif") {
+                let body_end = block.body.last().map_or(start, |last| usize::from(last.end()));
+                self.replacements.push((body_end, end, String::new()));
+            } else {
+                self.replacements.push((start, end, String::from("pass")));
+            }
+        }
+    }
+
+    fn stub_imports(&mut self, body: &[ast::Stmt]) -> bool {
+        let mut stubbed = false;
+
+        for statement in body {
+            let (stubs, kept) = match statement {
+                ast::Stmt::Import(import) => {
+                    let (desktop, kept): (Vec<&ast::Alias>, Vec<&ast::Alias>) = import
+                        .names
+                        .iter()
+                        .partition(|alias| DESKTOP_ONLY_MODULES.contains(&alias.name.split('.').next().unwrap_or("")));
+
+                    let stubs: Vec<String> = desktop
+                        .iter()
+                        .map(|alias| alias.asname.as_ref().map_or(alias.name.split('.').next().unwrap_or(""), |asname| asname.as_str()).to_string())
+                        .collect();
+                    let kept: Vec<String> = kept
+                        .iter()
+                        .map(|alias| match &alias.asname {
+                            Some(asname) => format!("{} as {}", alias.name, asname),
+                            None => alias.name.to_string(),
+                        })
+                        .collect();
+
+                    (stubs, (!kept.is_empty()).then(|| format!("import {}", kept.join(", "))))
+                }
+                ast::Stmt::ImportFrom(import)
+                    if import.module.as_deref().is_some_and(|module| DESKTOP_ONLY_MODULES.contains(&module.split('.').next().unwrap_or("")))
+                        && import.names.iter().all(|alias| alias.name.as_str() != "*") =>
+                {
+                    let stubs = import.names.iter().map(|alias| alias.asname.as_ref().unwrap_or(&alias.name).to_string()).collect();
+                    (stubs, None)
+                }
+                _ => {
+                    for suite in python::child_bodies(statement) {
+                        stubbed |= self.stub_imports(suite);
+                    }
+                    continue;
+                }
+            };
+
+            if stubs.is_empty() {
+                continue;
+            }
+
+            let start = usize::from(statement.start());
+            let prefix = self.line_prefix(start);
+            let separator = if prefix.trim().is_empty() { format!("\n{}", prefix) } else { String::from("; ") };
+
+            let replacement: Vec<String> = kept.into_iter().chain(stubs.iter().map(|name| format!("{} = _DesktopStub()", name))).collect();
+            self.replacements.push((start, usize::from(statement.end()), replacement.join(&separator)));
+            stubbed = true;
+        }
+
+        stubbed
+    }
+
+    fn apply(mut self) -> Vec<String> {
+        let mut rewritten = self.source.to_string();
+
+        self.replacements.sort_by_key(|(start, _, _)| *start);
+        for (start, end, replacement) in self.replacements.into_iter().rev() {
+            rewritten.replace_range(start..end, &replacement);
+        }
+
+        rewritten.split('\n').map(|line| line.to_string()).collect()
+    }
+}
+
+fn rewriter(parsed: &ParsedSource) -> Option<(Rewriter<'_>, &ast::Suite)> {
+    let suite = parsed.suite.as_ref().ok()?;
+
+    Some((Rewriter { source: &parsed.source, line_starts: &parsed.line_starts, replacements: Vec::new() }, suite))
+}
+
+pub fn strip_dev_guarded_blocks(lines: Vec<String>) -> Vec<String> {
+    let parsed = python::parse_source(&lines);
+
+    let Some((mut rewriter, suite)) = rewriter(&parsed) else {
+        return lines;
+    };
+
+    rewriter.strip_guards(suite);
+    rewriter.apply()
+}
+
+pub fn stub_desktop_imports(lines: Vec<String>) -> Vec<String> {
+    let parsed = python::parse_source(&lines);
+
+    let Some((mut rewriter, suite)) = rewriter(&parsed) else {
+        return lines;
+    };
+
+    if !rewriter.stub_imports(suite) {
+        return lines;
+    }
+
+    let mut stubbed_lines: Vec<String> = STUB_CLASS.iter().map(|line| line.to_string()).collect();
+    stubbed_lines.extend(rewriter.apply());
+
+    stubbed_lines
+}
@@ -0,0 +1,37 @@
+use std::collections::HashMap;
+
+#[derive(Default)]
+pub struct WarningGroups {
+    order: Vec<String>,
+    scripts: HashMap<String, Vec<String>>,
+}
+
+impl WarningGroups {
+    pub fn add(&mut self, script_name: &str, warning: &str) {
+        let scripts = self.scripts.entry(warning.to_string()).or_insert_with(|| {
+            self.order.push(warning.to_string());
+            Vec::new()
+        });
+
+        if !scripts.iter().any(|name| name == script_name) {
+            scripts.push(script_name.to_string());
+        }
+    }
+
+    pub fn render(&self, total_scripts: usize) -> Vec<String> {
+        self.order
+            .iter()
+            .map(|warning| {
+                let scripts = &self.scripts[warning];
+
+                if total_scripts <= 1 {
+                    format!("warning: {}", warning)
+                } else if scripts.len() == 1 {
+                    format!("warning: {} ({})", warning, scripts[0])
+                } else {
+                    format!("warning: {} ({} scripts: {})", warning, scripts.len(), scripts.join(", "))
+                }
+            })
+            .collect()
+    }
+}
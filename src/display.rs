@@ -0,0 +1,207 @@
+use regex::Regex;
+use rustpython_parser::{StringKind, Tok};
+use crate::python;
+use crate::subset::Profile;
+
+const OUTPUT_FUNCTIONS: &[&str] = &["print", "input"];
+
+pub struct DisplayReport {
+    pub lines: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+struct Argument<'a> {
+    tokens: &'a [(Tok, usize, usize)],
+    keyword: Option<&'a str>,
+}
+
+fn split_arguments(tokens: &[(Tok, usize, usize)]) -> Vec<Argument<'_>> {
+    let mut arguments = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+
+    for (index, (token, _, _)) in tokens.iter().enumerate() {
+        match token {
+            Tok::Lpar | Tok::Lsqb | Tok::Lbrace => depth += 1,
+            Tok::Rpar | Tok::Rsqb | Tok::Rbrace if depth > 0 => depth -= 1,
+            Tok::Comma | Tok::Rpar if depth == 0 => {
+                let argument = &tokens[start..index];
+
+                if !argument.is_empty() {
+                    let keyword = match argument {
+                        [(Tok::Name { name }, _, _), (Tok::Equal, _, _), ..] => Some(name.as_str()),
+                        _ => None,
+                    };
+                    arguments.push(Argument { tokens: argument, keyword });
+                }
+
+                if matches!(token, Tok::Rpar) {
+                    return arguments;
+                }
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+
+    arguments
+}
+
+fn literal_text(argument: &Argument) -> Option<(String, bool)> {
+    let [(Tok::String { value, kind, .. }, _, _)] = argument.tokens else {
+        return None;
+    };
+
+    match kind {
+        StringKind::String | StringKind::Unicode | StringKind::RawString => Some((value.clone(), true)),
+        StringKind::FString | StringKind::RawFString => {
+            let placeholders = Regex::new(r"\{[^{}]*\}").unwrap();
+            Some((placeholders.replace_all(value, "").to_string(), false))
+        }
+        _ => None,
+    }
+}
+
+fn longest_line(text: &str) -> usize {
+    text.split("\\n").map(|line| line.chars().count()).max().unwrap_or(0)
+}
+
+fn wrap_words(text: &str, columns: usize) -> String {
+    let mut wrapped: Vec<String> = Vec::new();
+    let mut current = String::new();
+
+    for word in text.split(' ') {
+        if !current.is_empty() && current.chars().count() + 1 + word.chars().count() > columns {
+            wrapped.push(std::mem::take(&mut current));
+        } else if !current.is_empty() {
+            current.push(' ');
+        }
+        current.push_str(word);
+    }
+    wrapped.push(current);
+
+    wrapped.join("\\n")
+}
+
+pub fn check_display(lines: Vec<String>, profile: &Profile, wrap_text: bool) -> DisplayReport {
+    let parsed = python::parse_source(&lines);
+    let (source, tokens) = (&parsed.source, &parsed.tokens);
+
+    let width_re = Regex::new(r"\{[^{}:]*:[^{}\d]*(\d+)[^{}]*\}").unwrap();
+
+    let mut replacements: Vec<(usize, usize, String)> = Vec::new();
+    let mut warnings = Vec::new();
+    let mut note = |offset: usize, message: String| warnings.push((parsed.line_index(offset) + 1, message));
+
+    for (token, start, _) in tokens {
+        let Tok::String { value, .. } = token else {
+            continue;
+        };
+
+        for caps in width_re.captures_iter(value) {
+            let width: usize = caps[1].parse().unwrap_or(0);
+
+            if width > profile.columns {
+                note(*start, format!("{} pads to {} characters, wider than the {}-column screen, so every line it formats will wrap", &caps[0], width, profile.columns));
+            }
+        }
+    }
+
+    for index in 0..tokens.len() {
+        let [(Tok::Name { name }, start, _), (Tok::Lpar, _, _)] = &tokens[index..(index + 2).min(tokens.len())] else {
+            continue;
+        };
+
+        if index > 0 && matches!(tokens[index - 1].0, Tok::Dot | Tok::Def) {
+            continue;
+        }
+
+        let arguments = split_arguments(&tokens[index + 2..]);
+
+        if OUTPUT_FUNCTIONS.contains(&name.as_str()) {
+            let positional: Vec<&Argument> = arguments.iter().filter(|argument| argument.keyword.is_none()).collect();
+            let literals: Vec<(String, bool)> = positional.iter().filter_map(|argument| literal_text(argument)).collect();
+
+            let width = if positional.len() == 1 {
+                literals.first().map(|(text, _)| longest_line(text)).unwrap_or(0)
+            } else {
+                literals.iter().map(|(text, _)| text.chars().count()).sum::<usize>() + positional.len().saturating_sub(1)
+            };
+
+            if width <= profile.columns {
+                continue;
+            }
+
+            let exact = literals.len() == positional.len() && literals.iter().all(|(_, exact)| *exact);
+
+            let wrappable = match (positional.as_slice(), arguments.len()) {
+                ([argument], 1) => match argument.tokens {
+                    [(Tok::String { value, kind: StringKind::String, triple_quoted: false }, literal_start, literal_end)] if !value.contains('\\') => {
+                        Some((*literal_start, *literal_end, value))
+                    }
+                    _ => None,
+                },
+                _ => None,
+            };
+
+            match wrappable.filter(|_| wrap_text && name == "print") {
+                Some((literal_start, literal_end, value)) => {
+                    let quote = &source[literal_start..literal_start + 1];
+                    replacements.push((literal_start, literal_end, format!("{}{}{}", quote, wrap_words(value, profile.columns), quote)));
+                    note(*start, format!("{}() message was wrapped at word boundaries to fit {} columns", name, profile.columns));
+                }
+                None => note(
+                    *start,
+                    format!(
+                        "{}() writes {}{} characters on one line; the calculator shell wraps at {} columns",
+                        name,
+                        if exact { "" } else { "at least " },
+                        width,
+                        profile.columns
+                    ),
+                ),
+            }
+        } else if name == "disp_at" {
+            if let Some([(Tok::Int { value }, _, _)]) = arguments.first().map(|argument| argument.tokens) {
+                let row = i64::try_from(value).unwrap_or(0);
+
+                if row < 1 || row > profile.rows as i64 {
+                    note(*start, format!("disp_at() row {} is off the screen; rows run from 1 to {}", row, profile.rows));
+                }
+            }
+
+            if let Some((text, exact)) = arguments.get(1).filter(|argument| argument.keyword.is_none()).and_then(literal_text) {
+                let width = longest_line(&text);
+
+                if width > profile.columns {
+                    note(
+                        *start,
+                        format!(
+                            "disp_at() text is {}{} characters, wider than the {}-column screen",
+                            if exact { "" } else { "at least " },
+                            width,
+                            profile.columns
+                        ),
+                    );
+                }
+            }
+        }
+    }
+
+    warnings.sort_by_key(|(line_number, _)| *line_number);
+    let warnings = warnings.into_iter().map(|(line_number, message)| format!("{}: {}", line_number, message)).collect();
+
+    if replacements.is_empty() {
+        return DisplayReport { lines, warnings };
+    }
+
+    let mut wrapped = source.to_string();
+    for (start, end, replacement) in replacements.into_iter().rev() {
+        wrapped.replace_range(start..end, &replacement);
+    }
+
+    DisplayReport {
+        lines: wrapped.split('\n').map(|line| line.to_string()).collect(),
+        warnings,
+    }
+}
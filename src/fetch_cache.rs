@@ -0,0 +1,62 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use crate::ast_cache::{content_hash, seal, unseal, write_atomically};
+
+pub struct CachedResponse {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub bytes: Vec<u8>,
+}
+
+pub struct FetchCache {
+    directory: PathBuf,
+}
+
+impl FetchCache {
+    pub fn from_env() -> Option<Self> {
+        let directory = match env::var("FETCH_CACHE_DIR") {
+            Ok(dir) if dir.is_empty() => return None,
+            Ok(dir) => PathBuf::from(dir),
+            Err(_) => env::var("XDG_CACHE_HOME")
+                .ok()
+                .filter(|dir| !dir.is_empty())
+                .map(PathBuf::from)
+                .or_else(|| env::var("HOME").ok().map(|home| PathBuf::from(home).join(".cache")))?
+                .join("ti84-bundler"),
+        };
+
+        Some(FetchCache { directory })
+    }
+
+    fn path(&self, url: &str) -> PathBuf {
+        self.directory.join(format!("{}.entry", content_hash(url)))
+    }
+
+    pub fn get(&self, url: &str) -> Option<CachedResponse> {
+        let entry = fs::read(self.path(url)).ok()?;
+        let mut fields = unseal(&entry)?.splitn(3, |byte| *byte == b'\n');
+        let mut header = || fields.next().map(|field| Some(String::from_utf8_lossy(field).to_string()).filter(|value| !value.is_empty()));
+
+        let etag = header()?;
+        let last_modified = header()?;
+        let bytes = fields.next()?.to_vec();
+
+        Some(CachedResponse { etag, last_modified, bytes })
+    }
+
+    pub fn put(&self, url: &str, etag: Option<&str>, last_modified: Option<&str>, bytes: &[u8]) {
+        if etag.is_none() && last_modified.is_none() {
+            return;
+        }
+
+        let mut entry = format!("{}\n{}\n", etag.unwrap_or(""), last_modified.unwrap_or("")).into_bytes();
+        entry.extend_from_slice(bytes);
+
+        let written = fs::create_dir_all(&self.directory).and_then(|_| write_atomically(&self.path(url), seal(entry)));
+
+        if let Err(err) = written {
+            eprintln!("warning: could not cache {}: {}", url, err);
+        }
+    }
+}
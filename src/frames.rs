@@ -0,0 +1,285 @@
+use std::collections::{HashMap, HashSet};
+use rustpython_parser::ast::{self, Ranged};
+use rustpython_parser::Tok;
+use crate::python;
+
+const DRAWING_MODULES: &[&str] = &["ti_draw", "ti_plotlib"];
+
+pub struct FrameEstimate {
+    pub line_number: usize,
+    pub calls: usize,
+    pub lower_bound: bool,
+}
+
+struct Cost {
+    calls: usize,
+    lower_bound: bool,
+}
+
+impl Cost {
+    fn add(&mut self, other: Cost) {
+        self.calls = self.calls.saturating_add(other.calls);
+        self.lower_bound |= other.lower_bound;
+    }
+}
+
+struct Estimator<'a> {
+    tokens: &'a [(Tok, usize, usize)],
+    module_aliases: HashSet<String>,
+    drawing_functions: HashSet<String>,
+    functions: HashMap<String, &'a [ast::Stmt]>,
+    constants: HashMap<String, i64>,
+    visiting: Vec<String>,
+}
+
+fn collect_imports(suite: &[ast::Stmt], module_aliases: &mut HashSet<String>, drawing_functions: &mut HashSet<String>) {
+    for statement in suite {
+        match statement {
+            ast::Stmt::Import(import) => {
+                for alias in import.names.iter().filter(|alias| DRAWING_MODULES.contains(&alias.name.as_str())) {
+                    module_aliases.insert(alias.asname.as_ref().unwrap_or(&alias.name).to_string());
+                }
+            }
+            ast::Stmt::ImportFrom(import) if import.module.as_deref().is_some_and(|module| DRAWING_MODULES.contains(&module)) => {
+                for alias in import.names.iter() {
+                    drawing_functions.insert(alias.asname.as_ref().unwrap_or(&alias.name).to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl<'a> Estimator<'a> {
+    fn int_value(&self, expr: &ast::Expr) -> Option<i64> {
+        match expr {
+            ast::Expr::Constant(constant) => match &constant.value {
+                ast::Constant::Int(value) => value.try_into().ok(),
+                _ => None,
+            },
+            ast::Expr::Name(name) => self.constants.get(name.id.as_str()).copied(),
+            ast::Expr::UnaryOp(unary) if matches!(unary.op, ast::UnaryOp::USub) => self.int_value(&unary.operand)?.checked_neg(),
+            _ => None,
+        }
+    }
+
+    fn iterations(&self, iter: &ast::Expr) -> Option<usize> {
+        match iter {
+            ast::Expr::List(list) => Some(list.elts.len()),
+            ast::Expr::Tuple(tuple) => Some(tuple.elts.len()),
+            ast::Expr::Call(call) if matches!(&*call.func, ast::Expr::Name(name) if name.id.as_str() == "range") => {
+                let values: Option<Vec<i64>> = call.args.iter().map(|arg| self.int_value(arg)).collect();
+
+                let (start, stop, step) = match values?.as_slice() {
+                    [stop] => (0, *stop, 1),
+                    [start, stop] => (*start, *stop, 1),
+                    [start, stop, step] if *step != 0 => (*start, *stop, *step),
+                    _ => return None,
+                };
+
+                let span = stop.checked_sub(start)?.checked_add(step - step.signum())?;
+                Some(span.checked_div(step)?.max(0) as usize)
+            }
+            _ => None,
+        }
+    }
+
+    fn statement_calls(&mut self, statement: &ast::Stmt) -> Cost {
+        let range = usize::from(statement.start())..usize::from(statement.end());
+        let tokens: Vec<(Tok, usize)> = self
+            .tokens
+            .iter()
+            .filter(|(_, offset, _)| range.contains(offset))
+            .map(|(token, offset, _)| (token.clone(), *offset))
+            .collect();
+
+        let mut cost = Cost { calls: 0, lower_bound: false };
+
+        for (index, window) in tokens.windows(2).enumerate() {
+            let [(Tok::Name { name }, _), (Tok::Lpar, _)] = window else {
+                continue;
+            };
+
+            let after_dot = index > 0 && matches!(tokens[index - 1].0, Tok::Dot);
+            let module_call = index > 1
+                && after_dot
+                && matches!(&tokens[index - 2].0, Tok::Name { name: module } if self.module_aliases.contains(module));
+
+            if module_call || (!after_dot && self.drawing_functions.contains(name)) {
+                cost.calls = cost.calls.saturating_add(1);
+            } else if !after_dot && !self.visiting.contains(name) {
+                if let Some(body) = self.functions.get(name.as_str()).copied() {
+                    self.visiting.push(name.clone());
+                    cost.add(self.body_cost(body));
+                    self.visiting.pop();
+                }
+            }
+        }
+
+        cost
+    }
+
+    fn body_cost(&mut self, body: &[ast::Stmt]) -> Cost {
+        let mut cost = Cost { calls: 0, lower_bound: false };
+
+        for statement in body {
+            let statement_cost = match statement {
+                ast::Stmt::FunctionDef(_) | ast::Stmt::AsyncFunctionDef(_) | ast::Stmt::ClassDef(_) => Cost { calls: 0, lower_bound: false },
+                ast::Stmt::For(block) => {
+                    let mut inner = self.body_cost(&block.body);
+
+                    match self.iterations(&block.iter) {
+                        Some(iterations) => inner.calls = inner.calls.saturating_mul(iterations),
+                        None => inner.lower_bound |= inner.calls > 0,
+                    }
+
+                    inner.add(self.body_cost(&block.orelse));
+                    inner
+                }
+                ast::Stmt::While(block) => {
+                    let mut inner = self.body_cost(&block.body);
+                    inner.lower_bound |= inner.calls > 0;
+                    inner
+                }
+                ast::Stmt::If(block) => {
+                    let body = self.body_cost(&block.body);
+                    let orelse = self.body_cost(&block.orelse);
+
+                    if body.calls >= orelse.calls {
+                        body
+                    } else {
+                        orelse
+                    }
+                }
+                ast::Stmt::With(block) => self.body_cost(&block.body),
+                ast::Stmt::Try(block) => {
+                    let mut inner = self.body_cost(&block.body);
+                    inner.add(self.body_cost(&block.orelse));
+                    inner.add(self.body_cost(&block.finalbody));
+                    inner
+                }
+                _ => self.statement_calls(statement),
+            };
+
+            cost.add(statement_cost);
+        }
+
+        cost
+    }
+
+    fn frame_loops(&mut self, body: &'a [ast::Stmt], estimates: &mut Vec<(usize, Cost)>) {
+        for statement in body {
+            match statement {
+                ast::Stmt::While(block) => estimates.push((usize::from(statement.start()), self.body_cost(&block.body))),
+                ast::Stmt::FunctionDef(def) => self.frame_loops(&def.body, estimates),
+                ast::Stmt::If(block) => {
+                    self.frame_loops(&block.body, estimates);
+                    self.frame_loops(&block.orelse, estimates);
+                }
+                ast::Stmt::Try(block) => self.frame_loops(&block.body, estimates),
+                ast::Stmt::With(block) => self.frame_loops(&block.body, estimates),
+                _ => {}
+            }
+        }
+    }
+}
+
+pub fn estimate_frames(lines: &[String]) -> Vec<FrameEstimate> {
+    let parsed = python::parse_source(lines);
+
+    let Ok(suite) = &parsed.suite else {
+        return Vec::new();
+    };
+
+    let mut module_aliases = HashSet::new();
+    let mut drawing_functions = HashSet::new();
+    collect_imports(suite, &mut module_aliases, &mut drawing_functions);
+
+    if module_aliases.is_empty() && drawing_functions.is_empty() {
+        return Vec::new();
+    }
+
+    let mut estimator = Estimator {
+        tokens: &parsed.tokens,
+        module_aliases,
+        drawing_functions,
+        functions: HashMap::new(),
+        constants: HashMap::new(),
+        visiting: Vec::new(),
+    };
+
+    for statement in suite {
+        match statement {
+            ast::Stmt::FunctionDef(def) => {
+                estimator.functions.insert(def.name.to_string(), &def.body);
+            }
+            ast::Stmt::Assign(assign) => {
+                if let ([ast::Expr::Name(target)], Some(value)) = (assign.targets.as_slice(), estimator.int_value(&assign.value)) {
+                    estimator.constants.insert(target.id.to_string(), value);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut estimates = Vec::new();
+    estimator.frame_loops(suite, &mut estimates);
+
+    estimates
+        .into_iter()
+        .filter(|(_, cost)| cost.calls > 0)
+        .map(|(offset, cost)| FrameEstimate {
+            line_number: parsed.line_index(offset) + 1,
+            calls: cost.calls,
+            lower_bound: cost.lower_bound,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame_calls(body: &str) -> Vec<(usize, usize, bool)> {
+        let mut lines = vec![String::from("import ti_draw"), String::from("while True:")];
+        lines.extend(body.lines().map(|line| format!("    {}", line)));
+
+        estimate_frames(&lines)
+            .into_iter()
+            .map(|estimate| (estimate.line_number, estimate.calls, estimate.lower_bound))
+            .collect()
+    }
+
+    #[test]
+    fn counts_ascending_ranges() {
+        assert_eq!(frame_calls("for i in range(2, 12, 3):\n    ti_draw.fill_rect(i, 0, 1, 1)"), vec![(2, 4, false)]);
+    }
+
+    #[test]
+    fn counts_descending_ranges() {
+        assert_eq!(frame_calls("for i in range(10, 0, -1):\n    ti_draw.fill_rect(i, 0, 1, 1)"), vec![(2, 10, false)]);
+        assert_eq!(frame_calls("for i in range(10, -1, -3):\n    ti_draw.fill_rect(i, 0, 1, 1)"), vec![(2, 4, false)]);
+    }
+
+    #[test]
+    fn empty_ranges_make_no_calls() {
+        assert!(frame_calls("for i in range(5, 5):\n    ti_draw.fill_rect(i, 0, 1, 1)").is_empty());
+        assert!(frame_calls("for i in range(0, 10, -1):\n    ti_draw.fill_rect(i, 0, 1, 1)").is_empty());
+    }
+
+    #[test]
+    fn overflowing_ranges_fall_back_to_a_lower_bound() {
+        assert_eq!(
+            frame_calls("for i in range(1, -9223372036854775807, -1):\n    ti_draw.fill_rect(i, 0, 1, 1)"),
+            vec![(2, 1, true)]
+        );
+        assert_eq!(
+            frame_calls("for i in range(-9223372036854775807, 9223372036854775807):\n    ti_draw.fill_rect(i, 0, 1, 1)"),
+            vec![(2, 1, true)]
+        );
+        assert_eq!(
+            frame_calls("for i in range(-9223372036854775808, 0):\n    ti_draw.fill_rect(i, 0, 1, 1)"),
+            vec![(2, 1, true)]
+        );
+    }
+}
@@ -0,0 +1,160 @@
+use crate::provenance::BundledLine;
+
+pub struct ImportEdge {
+    pub importer: String,
+    pub imported: String,
+    pub symbols: Vec<String>,
+    pub line_number: usize,
+}
+
+pub struct FileSize {
+    pub source: String,
+    pub lines: usize,
+    pub bytes: usize,
+}
+
+pub struct ScriptGraph {
+    pub script_name: String,
+    pub lines: usize,
+    pub bytes: usize,
+    pub files: Vec<FileSize>,
+    pub imports: Vec<ImportEdge>,
+}
+
+impl ScriptGraph {
+    pub fn new(script_name: &str, bundled_lines: &[BundledLine], output_lines: &[String], imports: Vec<ImportEdge>) -> Self {
+        let mut files: Vec<FileSize> = Vec::new();
+
+        for line in bundled_lines {
+            let Some(origin) = &line.origin else {
+                continue;
+            };
+
+            let index = match files.iter().position(|file| *file.source == *origin.source) {
+                Some(index) => index,
+                None => {
+                    files.push(FileSize { source: origin.source.to_string(), lines: 0, bytes: 0 });
+                    files.len() - 1
+                }
+            };
+
+            files[index].lines += 1;
+            files[index].bytes += line.text.len() + 1;
+        }
+
+        for edge in &imports {
+            for source in [&edge.importer, &edge.imported] {
+                if !files.iter().any(|file| file.source == *source) {
+                    files.push(FileSize { source: source.clone(), lines: 0, bytes: 0 });
+                }
+            }
+        }
+
+        ScriptGraph {
+            script_name: script_name.to_string(),
+            lines: output_lines.len(),
+            bytes: output_lines.join("\n").len(),
+            files,
+            imports,
+        }
+    }
+}
+
+fn dot_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+pub fn json_string(value: &str) -> String {
+    let mut escaped = String::from("\"");
+
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped.push('"');
+    escaped
+}
+
+pub fn render_dot(graphs: &[ScriptGraph]) -> String {
+    let mut lines = vec![
+        String::from("digraph bundle {"),
+        String::from("    rankdir=LR;"),
+        String::from("    node [shape=box];"),
+    ];
+
+    for graph in graphs {
+        let node_id = |source: &str| dot_string(&format!("{}:{}", graph.script_name, source));
+
+        lines.push(format!("    subgraph {} {{", dot_string(&format!("cluster_{}", graph.script_name))));
+        lines.push(format!(
+            "        label={};",
+            dot_string(&format!("{}.py ({} lines, {} bytes)", graph.script_name, graph.lines, graph.bytes))
+        ));
+
+        for file in &graph.files {
+            lines.push(format!(
+                "        {} [label={}];",
+                node_id(&file.source),
+                dot_string(&format!("{}\n{} lines, {} bytes", file.source, file.lines, file.bytes)).replace('\n', "\\n")
+            ));
+        }
+
+        for edge in &graph.imports {
+            lines.push(format!(
+                "        {} -> {} [label={}];",
+                node_id(&edge.importer),
+                node_id(&edge.imported),
+                dot_string(&edge.symbols.join(", "))
+            ));
+        }
+
+        lines.push(String::from("    }"));
+    }
+
+    lines.push(String::from("}"));
+    lines.join("\n")
+}
+
+pub fn render_json(graphs: &[ScriptGraph]) -> String {
+    let scripts: Vec<String> = graphs
+        .iter()
+        .map(|graph| {
+            let files: Vec<String> = graph
+                .files
+                .iter()
+                .map(|file| format!("{{\"path\":{},\"lines\":{},\"bytes\":{}}}", json_string(&file.source), file.lines, file.bytes))
+                .collect();
+
+            let imports: Vec<String> = graph
+                .imports
+                .iter()
+                .map(|edge| {
+                    let symbols: Vec<String> = edge.symbols.iter().map(|symbol| json_string(symbol)).collect();
+                    format!(
+                        "{{\"from\":{},\"to\":{},\"symbols\":[{}]}}",
+                        json_string(&edge.importer),
+                        json_string(&edge.imported),
+                        symbols.join(",")
+                    )
+                })
+                .collect();
+
+            format!(
+                "{{\"name\":{},\"lines\":{},\"bytes\":{},\"files\":[{}],\"imports\":[{}]}}",
+                json_string(&graph.script_name),
+                graph.lines,
+                graph.bytes,
+                files.join(","),
+                imports.join(",")
+            )
+        })
+        .collect();
+
+    format!("{{\"scripts\":[{}]}}", scripts.join(","))
+}
@@ -0,0 +1,102 @@
+use regex::Regex;
+use crate::relative_source;
+
+pub const ATTRIBUTION_HEADER: &str = "# Bundled sources are licensed as follows:";
+
+pub struct LicenseNotice {
+    pub source: String,
+    pub expression: String,
+}
+
+enum LicenseKind {
+    StrongCopyleft,
+    Proprietary,
+    Other,
+}
+
+fn classify_license(identifier: &str) -> LicenseKind {
+    if identifier.starts_with("LicenseRef-") || identifier.eq_ignore_ascii_case("proprietary") {
+        LicenseKind::Proprietary
+    } else if identifier.starts_with("GPL-") || identifier.starts_with("AGPL-") {
+        LicenseKind::StrongCopyleft
+    } else {
+        LicenseKind::Other
+    }
+}
+
+pub fn record_license_tags(notices: &mut Vec<LicenseNotice>, source: &str, lines: &[String]) {
+    if notices.iter().any(|notice| notice.source == source) {
+        return;
+    }
+
+    let spdx_re = Regex::new(r"SPDX-License-Identifier:\s*(.+)").unwrap();
+
+    for line in lines {
+        if let Some(caps) = spdx_re.captures(line) {
+            notices.push(LicenseNotice {
+                source: source.to_string(),
+                expression: caps[1].trim().to_string(),
+            });
+        }
+    }
+}
+
+pub fn attribution_block(notices: &[LicenseNotice], project: &str) -> Vec<String> {
+    if notices.is_empty() {
+        return Vec::new();
+    }
+
+    let mut block = vec![ATTRIBUTION_HEADER.to_string()];
+
+    for notice in notices {
+        block.push(format!("#   {}: {}", relative_source(&notice.source, project), notice.expression));
+    }
+
+    block.push(String::new());
+
+    block
+}
+
+pub fn check_license_compatibility(notices: &[LicenseNotice], project: &str) -> Vec<String> {
+    let mut warnings = Vec::new();
+
+    let single_licensed: Vec<&LicenseNotice> = notices
+        .iter()
+        .filter(|notice| !notice.expression.contains(" OR "))
+        .collect();
+
+    for (index, first) in single_licensed.iter().enumerate() {
+        for second in &single_licensed[index + 1..] {
+            for first_id in license_identifiers(&first.expression) {
+                for second_id in license_identifiers(&second.expression) {
+                    if licenses_conflict(first_id, second_id) {
+                        warnings.push(format!(
+                            "{} ({}) is incompatible with {} ({}) in the same bundle",
+                            relative_source(&first.source, project),
+                            first_id,
+                            relative_source(&second.source, project),
+                            second_id
+                        ));
+                    }
+                }
+            }
+        }
+    }
+
+    warnings
+}
+
+fn license_identifiers(expression: &str) -> impl Iterator<Item = &str> {
+    expression
+        .split(|c: char| c.is_whitespace() || c == '(' || c == ')')
+        .filter(|token| !token.is_empty() && *token != "AND" && *token != "WITH")
+}
+
+fn licenses_conflict(first: &str, second: &str) -> bool {
+    let kinds = (classify_license(first), classify_license(second));
+
+    match kinds {
+        (LicenseKind::Proprietary, LicenseKind::StrongCopyleft) | (LicenseKind::StrongCopyleft, LicenseKind::Proprietary) => true,
+        _ => (first == "GPL-2.0-only" && second == "Apache-2.0") || (first == "Apache-2.0" && second == "GPL-2.0-only"),
+    }
+}
@@ -0,0 +1,71 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+use crate::ast_cache::{content_hash, seal, unseal, write_atomically};
+
+pub struct CachedListing {
+    pub etag: Option<String>,
+    pub entries: Vec<String>,
+    pub fresh: bool,
+}
+
+pub struct ListingCache {
+    directory: PathBuf,
+    ttl: Duration,
+    refresh: bool,
+}
+
+impl ListingCache {
+    pub fn from_env(refresh: bool) -> Option<Self> {
+        let ttl = env::var("LISTING_CACHE_TTL").ok().and_then(|value| value.parse().ok()).unwrap_or(300);
+
+        if ttl == 0 {
+            return None;
+        }
+
+        let directory = env::var("LISTING_CACHE_DIR")
+            .ok()
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .unwrap_or_else(|| env::temp_dir().join("ti-84-python-compiler-listings"));
+
+        Some(ListingCache { directory, ttl: Duration::from_secs(ttl), refresh })
+    }
+
+    fn path(&self, url: &str) -> PathBuf {
+        self.directory.join(content_hash(url))
+    }
+
+    pub fn get(&self, url: &str) -> Option<CachedListing> {
+        let path = self.path(url);
+        let entry = fs::read(&path).ok()?;
+        let content = std::str::from_utf8(unseal(&entry)?).ok()?;
+        let mut lines = content.lines();
+
+        let etag = lines.next().filter(|etag| !etag.is_empty()).map(|etag| etag.to_string());
+        let entries = lines.map(|line| line.to_string()).collect();
+
+        let age = fs::metadata(&path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok());
+
+        Some(CachedListing {
+            etag,
+            entries,
+            fresh: !self.refresh && age.is_some_and(|age| age < self.ttl),
+        })
+    }
+
+    pub fn put(&self, url: &str, etag: Option<&str>, entries: &[String]) {
+        let mut lines = vec![etag.unwrap_or("").to_string()];
+        lines.extend(entries.iter().cloned());
+
+        let written = fs::create_dir_all(&self.directory).and_then(|_| write_atomically(&self.path(url), seal(lines.join("\n"))));
+
+        if let Err(err) = written {
+            eprintln!("warning: could not cache the listing of {}: {}", url, err);
+        }
+    }
+}
@@ -1,231 +1,1750 @@
-use std::collections::HashSet;
+mod appvar;
+mod archive;
+mod ast_cache;
+mod builtins;
+mod cli;
+mod clipboard;
+mod dead_code;
+mod desktop;
+mod diagnostics;
+mod display;
+mod fetch_cache;
+mod frames;
+mod graph;
+mod intern;
+mod license;
+mod listing_cache;
+mod mangle;
+mod minify;
+mod modules;
+mod pack;
+mod paging;
+mod perf;
+mod provenance;
+mod python;
+mod rules;
+mod scoping;
+mod shims;
+mod side_effects;
+mod sizes;
+mod sink;
+mod snapshot;
+mod source;
+mod subset;
+mod suggest;
+mod timing;
+mod tools;
+mod transfer;
+mod watch;
+mod watermark;
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::env;
-use base64::{Engine as _, engine::{general_purpose}};
-use std::io::{Write};
-use zip::write::{FileOptions, ZipWriter};
-use std::io::Cursor;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use clap::Parser;
 use dotenv::dotenv;
-use regex::Regex;
-use reqwest::blocking::Client;
+use license::LicenseNotice;
+use graph::{ImportEdge, ScriptGraph};
+use intern::Interner;
+use provenance::BundledLine;
+use python::{ImportedName, Statement, StatementKind};
+use sink::OutputSink;
+use source::Source;
+use cli::{BuildOptions, BundleArgs, CheckArgs, Cli, Command, PackArgs, ScriptSelection, SnapshotArgs, TransferArgs};
+
+const MAX_PARALLEL_FETCHES: usize = 8;
 
 struct PathsMap {
     download: String,
-    script: String,
+    entry_label: String,
+    group: String,
     project: String,
     common_helpers: String
 }
 
+impl PathsMap {
+    fn label(&self, path: &str) -> String {
+        if path == self.download {
+            return self.entry_label.clone();
+        }
+
+        relative_source(path, &self.project).to_string()
+    }
+}
+
+struct BundleContext<'a> {
+    source: &'a dyn Source,
+    project: String,
+    licenses: Vec<LicenseNotice>,
+    warnings: Vec<String>,
+    deadline: Option<Instant>,
+    interner: Interner,
+    definitions: HashMap<Arc<str>, (Arc<str>, usize)>,
+    emitted: HashSet<(Arc<str>, usize)>,
+    bundled_modules: HashSet<String>,
+    import_stack: Vec<String>,
+    imports: Vec<ImportEdge>,
+    prefetched: HashMap<String, Result<Vec<String>, String>>,
+    max_import_depth: usize,
+}
+
+struct ResolvedBundle {
+    lines: Vec<BundledLine>,
+    warnings: Vec<String>,
+    imports: Vec<ImportEdge>,
+}
+
+impl BundleContext<'_> {
+    fn read_lines(&self, path: &str) -> Result<Vec<String>, String> {
+        match self.prefetched.get(path) {
+            Some(lines) => lines.clone(),
+            None => read_lines_before(self.source, path, self.deadline, &self.project),
+        }
+    }
+
+    fn record_source(&mut self, source: &str, lines: &[String]) {
+        license::record_license_tags(&mut self.licenses, source, lines);
+    }
+
+    fn tag_line(&mut self, source: &str, line: &str, line_number: usize) -> BundledLine {
+        BundledLine::new(self.interner.intern(line), self.interner.intern(source), line_number)
+    }
+
+    fn tag_lines(&mut self, source: &str, lines: &[String], line_numbers: &[usize]) -> Vec<BundledLine> {
+        let source = relative_source(source, &self.project).to_string();
+
+        lines
+            .iter()
+            .zip(line_numbers)
+            .map(|(line, line_number)| self.tag_line(&source, line, *line_number))
+            .collect()
+    }
+
+    fn statement_lines(&mut self, file: &[String], statement: &Statement, source: &str) -> Vec<BundledLine> {
+        (statement.first_line..=statement.last_line)
+            .map(|index| self.tag_line(source, &file[index], index + 1))
+            .collect()
+    }
+
+    fn record_definitions(&mut self, source: &str, statements: &[&Statement]) {
+        let source = self.interner.intern(source);
+
+        for statement in statements.iter().filter(|statement| !statement.is_import()) {
+            for name in statement.bound_names() {
+                let name = self.interner.intern(name);
+                let line_number = statement.first_line + 1;
+
+                if let Some((defining_source, defining_line)) = self.definitions.get(&name).filter(|(defining_source, _)| *defining_source != source) {
+                    self.warnings.push(format!(
+                        "{}:{}: {} replaces the definition at {}:{} once inlined",
+                        source, line_number, name, defining_source, defining_line
+                    ));
+                }
+
+                self.definitions.insert(name, (source.clone(), line_number));
+            }
+        }
+    }
+
+    fn warn(&mut self, source: &str, message: String) {
+        self.warnings.push(format!("{}: {}", relative_source(source, &self.project), message));
+    }
+
+    fn check_shadowing(&mut self, source: &str, lines: &[String], line_numbers: &[usize]) {
+        for shadowing in builtins::find_shadowed_builtins(lines) {
+            self.warnings.push(format!(
+                "{}:{}: {} shadows the {} builtin for the whole bundle",
+                relative_source(source, &self.project),
+                line_numbers[shadowing.index],
+                shadowing.name,
+                shadowing.builtin_kind
+            ));
+        }
+    }
+
+    fn check_side_effects(&mut self, source: &str, lines: &[String], line_numbers: &[usize]) {
+        for side_effect in side_effects::find_module_level_side_effects(lines) {
+            let message = if side_effect.is_main_guard {
+                "__name__ guard is always true once inlined, so its body will run"
+            } else {
+                "module-level statement will run when inlined"
+            };
+
+            self.warnings.push(format!(
+                "{}:{}: {}: {}",
+                relative_source(source, &self.project),
+                line_numbers[side_effect.index],
+                message,
+                side_effect.statement
+            ));
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Preview {
+    None,
+    Plain,
+    Annotated,
+}
+
+struct BatchOutput {
+    files: Vec<FileObject>,
+    has_violations: bool,
+    failed: Vec<String>,
+    total: usize,
+    graphs: Vec<ScriptGraph>,
+    sizes: Vec<sizes::ScriptSize>,
+    size_record: Option<BTreeMap<String, usize>>,
+}
+
+#[derive(Clone)]
 struct FileObject {
     script_name: String,
     contents: Vec<String>,
 }
 
-fn gather_args() -> Vec<String> {
-    let args: Vec<String> = env::args().collect();
+fn relative_source<'a>(source: &'a str, project: &str) -> &'a str {
+    source.strip_prefix(project).unwrap_or(source).trim_start_matches('/')
+}
 
-    if args.len() <= 2 && args.len() >= 4 {
-        eprintln!("Usage: {} <group_name> <script_name>", args[0]);
-        std::process::exit(1);
+fn describe_paths(source: &dyn Source, group_name: &String, script_name: &String) -> Result<PathsMap, String> {
+    let root_directory = source.root().to_string();
+
+    let download = format!("{}/{}/{}/download.py", root_directory, group_name, script_name);
+
+    let paths = PathsMap {
+        entry_label: relative_source(&download, &root_directory).to_string(),
+        download,
+        group: group_name.to_string(),
+        common_helpers: format!("{}/common/helpers.py", root_directory),
+        project: root_directory
+    };
+
+    for path in [&paths.download, &paths.common_helpers] {
+        source.check_within_root(path)?;
+    }
+
+    Ok(paths)
+}
+
+fn read_lines_before(source: &dyn Source, path: &str, deadline: Option<Instant>, project: &str) -> Result<Vec<String>, String> {
+    let Some(deadline) = deadline else {
+        return source.read_lines(path);
+    };
+
+    let remaining = deadline.saturating_duration_since(Instant::now());
+
+    if remaining.is_zero() {
+        return Err(format!("exceeded --timeout before reading {}", relative_source(path, project)));
     }
 
-    args
+    source.read_lines_within(path, remaining)
 }
 
-fn fetch_file_content(url: &str) -> Vec<String> {
-    let client = Client::new();
-    let response = client.get(url).send().unwrap();
-    let content = response.text().unwrap();
-    content.lines().map(|line| line.to_string()).collect()
+fn build_bundle(source: &dyn Source, paths: &PathsMap, deadline: Option<Instant>, max_import_depth: usize) -> Result<ResolvedBundle, String> {
+    let entry_file = read_lines_before(source, &paths.download, deadline, &paths.project)?;
+
+    bundle_entry_lines(source, paths, entry_file, deadline, max_import_depth)
 }
 
-fn describe_paths(group_name: &String, script_name: &String) -> PathsMap {
-    let root_directory = env::var("ROOT_DIRECTORY").expect("ROOT_DIRECTORY not set");
+fn bundle_entry_lines(source: &dyn Source, paths: &PathsMap, entry_file: Vec<String>, deadline: Option<Instant>, max_import_depth: usize) -> Result<ResolvedBundle, String> {
+    let mut context = BundleContext {
+        source,
+        project: paths.project.clone(),
+        licenses: Vec::new(),
+        warnings: Vec::new(),
+        deadline,
+        interner: Interner::default(),
+        definitions: HashMap::new(),
+        emitted: HashSet::new(),
+        bundled_modules: HashSet::from([paths.download.clone()]),
+        import_stack: vec![paths.download.clone()],
+        imports: Vec::new(),
+        prefetched: prefetch_imports(source, paths, &entry_file, deadline, max_import_depth),
+        max_import_depth,
+    };
 
-    PathsMap {
-        download: format!("{}/{}/{}/download.py", root_directory, group_name, script_name),
-        script: format!("{}/{}/{}/script.py", root_directory, group_name, script_name),
-        common_helpers: format!("{}/common/helpers.py", root_directory),
-        project: format!("{}", root_directory)
+    context.record_source(&paths.entry_label, &entry_file);
+
+    let line_numbers: Vec<usize> = (1..=entry_file.len()).collect();
+    context.check_shadowing(&paths.entry_label, &entry_file, &line_numbers);
+
+    let bundled_output_lines = bundle_module_lines(&paths.download, &paths.entry_label, entry_file, paths, &mut context)?;
+
+    let license_warnings = license::check_license_compatibility(&context.licenses, &paths.project);
+    context.warnings.extend(license_warnings);
+
+    let mut output_lines: Vec<BundledLine> = license::attribution_block(&context.licenses, &paths.project)
+        .into_iter()
+        .map(BundledLine::generated)
+        .collect();
+    output_lines.extend(bundled_output_lines);
+
+    Ok(ResolvedBundle {
+        lines: output_lines,
+        warnings: context.warnings,
+        imports: context.imports,
+    })
+}
+
+fn imported_module_paths(importer: &str, file: &[String], paths: &PathsMap, source: &dyn Source) -> Vec<String> {
+    python::parse_statements(file, importer)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|statement| match &statement.kind {
+            StatementKind::ImportFrom { module, level: 0, .. } if module == "common.helpers" => Some(paths.common_helpers.clone()),
+            StatementKind::ImportFrom { module, level, .. } if is_project_module(module, *level, &paths.group) => {
+                resolve_module_path(importer, module, *level, paths, source).ok()
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+fn prefetch_imports(source: &dyn Source, paths: &PathsMap, entry_file: &[String], deadline: Option<Instant>, max_import_depth: usize) -> HashMap<String, Result<Vec<String>, String>> {
+    let mut prefetched: HashMap<String, Result<Vec<String>, String>> = HashMap::new();
+    let mut frontier = imported_module_paths(&paths.download, entry_file, paths, source);
+    let mut depth = 1;
+
+    while !frontier.is_empty() && depth <= max_import_depth {
+        frontier.sort();
+        frontier.dedup();
+        frontier.retain(|path| *path != paths.download && !prefetched.contains_key(path));
+
+        let mut next = Vec::new();
+
+        for batch in frontier.chunks(MAX_PARALLEL_FETCHES) {
+            let fetched: Vec<(String, Result<Vec<String>, String>)> = std::thread::scope(|scope| {
+                let handles: Vec<_> = batch
+                    .iter()
+                    .map(|path| scope.spawn(move || (path.clone(), read_lines_before(source, path, deadline, &paths.project))))
+                    .collect();
+
+                handles.into_iter().filter_map(|handle| handle.join().ok()).collect()
+            });
+
+            for (path, lines) in fetched {
+                if let Ok(lines) = &lines {
+                    next.extend(imported_module_paths(&path, lines, paths, source));
+                }
+                prefetched.insert(path, lines);
+            }
+        }
+
+        frontier = next;
+        depth += 1;
     }
+
+    prefetched
+}
+
+fn imported_symbols(names: &[ImportedName]) -> HashSet<String> {
+    names.iter().map(|imported| imported.name.clone()).collect()
+}
+
+fn alias_assignments(names: &[ImportedName]) -> Vec<BundledLine> {
+    names
+        .iter()
+        .filter_map(|imported| {
+            let alias = imported.alias.as_ref().filter(|alias| **alias != imported.name)?;
+            Some(BundledLine::generated(format!("{} = {}", alias, imported.name)))
+        })
+        .collect()
 }
 
-fn build_bundle(paths: &PathsMap) -> Vec<String> {
-    let mut bundled_output_lines = Vec::new();
+fn split_imports(line_count: usize, statements: &[Statement]) -> Vec<(usize, Option<&Statement>)> {
+    let mut segments = Vec::new();
+    let mut imports = statements.iter().filter(|statement| statement.is_import()).peekable();
+    let mut index = 0;
+
+    while index < line_count {
+        while imports.next_if(|statement| statement.first_line < index).is_some() {}
+
+        match imports.next_if(|statement| statement.first_line == index) {
+            Some(statement) => {
+                segments.push((index, Some(statement)));
+                index = statement.last_line + 1;
+            }
+            None => {
+                segments.push((index, None));
+                index += 1;
+            }
+        }
+    }
 
-    let entry_file = fetch_file_content(&paths.download);
+    segments
+}
+
+fn capture_definitions(file: &[String], statements: &[Statement], names: &HashSet<String>) -> (Vec<String>, Vec<usize>) {
+    let mut output_lines = Vec::new();
+    let mut line_numbers = Vec::new();
 
-    for line in entry_file {
-        if !line.starts_with("import") && !line.starts_with("from") {
-            bundled_output_lines.push(line);
+    for statement in statements {
+        if statement.is_import() || !statement.bound_names().iter().any(|name| names.contains(*name)) {
             continue;
         }
 
-        if line.contains("common.helpers") {
-            let lines = bundle_common_import_lines(&line, &paths.common_helpers);
-            bundled_output_lines.extend(lines);
-        } else if line.contains(".script") {
-            let lines = bundle_script_import_lines(&line, &paths);
-            bundled_output_lines.extend(lines);
+        let mut last_line = statement.last_line;
+        if file.get(last_line + 1).is_some_and(|line| line.trim().is_empty()) {
+            last_line += 1;
         }
+
+        output_lines.extend(file[statement.first_line..=last_line].iter().cloned());
+        line_numbers.extend(statement.first_line + 1..=last_line + 1);
     }
 
-    bundled_output_lines
+    (output_lines, line_numbers)
 }
 
-fn extract_function_names_from_import(line: &String) -> HashSet<String> {
-    let import_re = Regex::new(r"from \S+ import (.+)").unwrap();
+fn bundle_common_import_lines(imported: &[ImportedName], common_helpers: &str, context: &mut BundleContext) -> Result<Vec<BundledLine>, String> {
+    let functions_to_include = imported_symbols(imported);
 
-    let mut functions_to_include = HashSet::new();
+    let file = context.read_lines(common_helpers)?;
+    context.record_source(common_helpers, &file);
 
-    if let Some(caps) = import_re.captures(line) {
-        let functions_str = &caps[1];
+    let statements = python::parse_statements(&file, relative_source(common_helpers, &context.project))?;
 
-        for function in functions_str.split(',').map(|s| s.trim()) {
-            functions_to_include.insert(function.to_string());
+    let mut names_to_capture = functions_to_include.clone();
+    let (output_lines, line_numbers) = loop {
+        let (output_lines, line_numbers) = capture_definitions(&file, &statements, &names_to_capture);
+
+        let captured_count = names_to_capture.len();
+        names_to_capture.extend(scoping::referenced_module_state(&file, &output_lines));
+
+        if names_to_capture.len() == captured_count {
+            break (output_lines, line_numbers);
         }
+    };
+
+    for warning in scoping::check_global_references(&file, &output_lines, &functions_to_include) {
+        context.warn(common_helpers, warning);
     }
 
-    functions_to_include
-}
+    let (output_lines, line_numbers): (Vec<String>, Vec<usize>) = output_lines
+        .into_iter()
+        .zip(line_numbers)
+        .filter(|(_, line_number)| {
+            let source = context.interner.intern(common_helpers);
+            context.emitted.insert((source, *line_number))
+        })
+        .unzip();
+
+    context.check_side_effects(common_helpers, &output_lines, &line_numbers);
+    context.check_shadowing(common_helpers, &output_lines, &line_numbers);
+
+    let captured: Vec<&Statement> = statements
+        .iter()
+        .filter(|statement| line_numbers.contains(&(statement.first_line + 1)))
+        .collect();
+    let helpers_source = relative_source(common_helpers, &context.project).to_string();
+    context.record_definitions(&helpers_source, &captured);
+
+    let mut bundled_lines = context.tag_lines(common_helpers, &output_lines, &line_numbers);
+    bundled_lines.extend(alias_assignments(imported));
 
-fn bundle_common_import_lines(line: &String, common_helpers: &String) -> Vec<String> {
-    let functions_to_include = extract_function_names_from_import(line);
+    Ok(bundled_lines)
+}
 
-    let file = fetch_file_content(common_helpers);
+fn bundle_module_lines(module_path: &str, module_source: &str, file: Vec<String>, paths: &PathsMap, context: &mut BundleContext) -> Result<Vec<BundledLine>, String> {
     let mut output_lines = Vec::new();
-    let mut capture = false;
-    let mut indent_level = None;
-
-    for line in file {
-        if let Some(caps) = Regex::new(r"^def (\w+)\(").unwrap().captures(&line) {
-            let func_name = &caps[1];
-            if functions_to_include.contains(func_name) {
-                capture = true;
-                indent_level = Some(line.find(|c: char| !c.is_whitespace()).unwrap_or(0));
-            } else {
-                capture = false;
+    let statements = python::parse_statements(&file, module_source)?;
+
+    for (index, import) in split_imports(file.len(), &statements) {
+        let Some(statement) = import else {
+            if let Ok(position) = statements.binary_search_by_key(&index, |statement| statement.first_line) {
+                context.record_definitions(module_source, &[&statements[position]]);
+            }
+            output_lines.push(context.tag_line(module_source, &file[index], index + 1));
+            continue;
+        };
+
+        match &statement.kind {
+            StatementKind::ImportFrom { module, level: 0, names } if module == "common.helpers" => {
+                context.imports.push(ImportEdge {
+                    importer: module_source.to_string(),
+                    imported: paths.label(&paths.common_helpers),
+                    symbols: names.iter().map(|imported| imported.name.clone()).collect(),
+                    line_number: statement.first_line + 1,
+                });
+
+                let helper_lines = bundle_common_import_lines(names, &paths.common_helpers, context)?;
+                output_lines.extend(helper_lines);
+            }
+            StatementKind::ImportFrom { module, level, names } if is_project_module(module, *level, &paths.group) => {
+                let imported_path = resolve_module_path(module_path, module, *level, paths, context.source)?;
+
+                context.imports.push(ImportEdge {
+                    importer: module_source.to_string(),
+                    imported: paths.label(&imported_path),
+                    symbols: names.iter().map(|imported| imported.name.clone()).collect(),
+                    line_number: statement.first_line + 1,
+                });
+
+                if let Some(position) = context.import_stack.iter().position(|path| *path == imported_path) {
+                    let chain: Vec<String> = context.import_stack[position..]
+                        .iter()
+                        .chain([&imported_path])
+                        .map(|path| paths.label(path))
+                        .collect();
+
+                    context.warnings.push(format!(
+                        "{}:{}: circular import {}; the import is skipped because {} is still being inlined",
+                        module_source,
+                        statement.first_line + 1,
+                        chain.join(" -> "),
+                        paths.label(&imported_path)
+                    ));
+                } else if !context.bundled_modules.contains(&imported_path) {
+                    if context.import_stack.len() > context.max_import_depth {
+                        let chain: Vec<String> = context.import_stack.iter().chain([&imported_path]).map(|path| paths.label(path)).collect();
+
+                        return Err(format!(
+                            "import chain {} is deeper than --max-import-depth {}",
+                            chain.join(" -> "),
+                            context.max_import_depth
+                        ));
+                    }
+
+                    context.bundled_modules.insert(imported_path.clone());
+                    context.import_stack.push(imported_path.clone());
+                    let imported_lines = bundle_imported_module_lines(&imported_path, paths, context);
+                    context.import_stack.pop();
+
+                    output_lines.extend(imported_lines?);
+                }
+
+                output_lines.extend(alias_assignments(names));
             }
+            _ => output_lines.extend(context.statement_lines(&file, statement, module_source)),
         }
+    }
 
-        if let Some(caps) = Regex::new(r"^([A-Z_]+)\s*=").unwrap().captures(&line) {
-            let var_name = &caps[1];
-            if functions_to_include.contains(var_name) {
-                capture = true;
-                indent_level = Some(line.find(|c: char| !c.is_whitespace()).unwrap_or(0));
-            } else {
-                capture = false;
+    Ok(output_lines)
+}
+
+fn bundle_imported_module_lines(module_path: &str, paths: &PathsMap, context: &mut BundleContext) -> Result<Vec<BundledLine>, String> {
+    let file = context.read_lines(module_path)?;
+    context.record_source(module_path, &file);
+
+    let line_numbers: Vec<usize> = (1..=file.len()).collect();
+    context.check_side_effects(module_path, &file, &line_numbers);
+    context.check_shadowing(module_path, &file, &line_numbers);
+
+    let module_source = relative_source(module_path, &paths.project).to_string();
+
+    bundle_module_lines(module_path, &module_source, file, paths, context)
+}
+
+fn is_project_module(module: &str, level: usize, group: &str) -> bool {
+    if module.is_empty() {
+        return false;
+    }
+
+    if level > 0 {
+        return true;
+    }
+
+    match module.split_once('.') {
+        Some((package, _)) => package == group || package == "common",
+        None => false,
+    }
+}
+
+fn resolve_module_path(importer: &str, module: &str, level: usize, paths: &PathsMap, source: &dyn Source) -> Result<String, String> {
+    let is_identifier = |segment: &str| {
+        segment.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+            && segment.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    };
+
+    if !module.split('.').all(is_identifier) {
+        return Err(format!("Refusing to resolve import with a non-module path: {}", module));
+    }
+
+    let base = if level == 0 {
+        paths.project.clone()
+    } else {
+        let mut base = importer;
+        for _ in 0..level {
+            base = base.rsplit_once('/').map(|(parent, _)| parent).unwrap_or("");
+        }
+        base.to_string()
+    };
+
+    let module_path = format!("{}/{}.py", base, module.replace('.', "/"));
+
+    source.check_within_root(&module_path)?;
+
+    Ok(module_path)
+}
+
+fn run_extract(source: &dyn Source, module_path: &str, symbol: &str) {
+    let root_directory = source.root();
+
+    let module_file = format!("{}/{}.py", root_directory, module_path.replace('.', "/"));
+
+    if let Err(err) = source.check_within_root(&module_file) {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
+
+    let file = source.read_lines(&module_file).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    });
+
+    let statements = python::parse_statements(&file, relative_source(&module_file, root_directory)).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    });
+
+    let mut names_to_capture = HashSet::from([symbol.to_string()]);
+    let captured_lines = loop {
+        let (captured_lines, _) = capture_definitions(&file, &statements, &names_to_capture);
+
+        let captured_count = names_to_capture.len();
+        names_to_capture.extend(scoping::referenced_definitions(&file, &captured_lines));
+
+        if names_to_capture.len() == captured_count {
+            break captured_lines;
+        }
+    };
+
+    if captured_lines.is_empty() {
+        eprintln!("{} is not defined at module level in {}", symbol, relative_source(&module_file, root_directory));
+        std::process::exit(1);
+    }
+
+    let imports = scoping::required_imports(&file, &captured_lines);
+
+    for line in &imports {
+        println!("{}", line);
+    }
+
+    if !imports.is_empty() {
+        println!();
+    }
+
+    for line in &captured_lines {
+        println!("{}", line);
+    }
+}
+
+fn resolve_script(source: &dyn Source, selection: &ScriptSelection, script_name: &String, stdin_entry: Option<&Vec<String>>, options: &BuildOptions) -> Result<ResolvedBundle, String> {
+    let timeout = options.timeout.map(Duration::from_secs);
+    let deadline = timeout.map(|timeout| Instant::now() + timeout);
+    let mut paths = describe_paths(source, &selection.group, script_name)?;
+
+    match stdin_entry {
+        Some(entry_file) => {
+            paths.entry_label = String::from("<stdin>");
+            bundle_entry_lines(source, &paths, entry_file.clone(), deadline, options.max_import_depth)
+        }
+        None => build_bundle(source, &paths, deadline, options.max_import_depth),
+    }
+}
+
+fn build_scripts(source: &dyn Source, selection: &ScriptSelection, options: &BuildOptions, preview: Preview) -> BatchOutput {
+    let rules = options.ruleset.as_deref().map(|name| {
+        rules::ruleset(name).unwrap_or_else(|| {
+            eprintln!("Unknown ruleset: {}", name);
+            std::process::exit(1);
+        })
+    });
+
+    let target_profile = options.target_os.as_deref().map(|os_version| {
+        subset::profile(os_version).unwrap_or_else(|| {
+            eprintln!("Unknown --target-os: {} (expected one of {})", os_version, subset::os_versions().join(", "));
+            std::process::exit(1);
+        })
+    });
+
+    let desktop_mode = options.desktop_modules.as_deref().map(|name| {
+        desktop::parse_mode(name).unwrap_or_else(|| {
+            eprintln!("Unknown --desktop-modules mode: {} (expected stub or strip)", name);
+            std::process::exit(1);
+        })
+    });
+
+    let stdin_entry = selection.stdin.then(|| {
+        let mut entry_source = String::new();
+        std::io::stdin().read_to_string(&mut entry_source).unwrap_or_else(|err| {
+            eprintln!("Failed to read entry script from stdin: {}", err);
+            std::process::exit(1);
+        });
+        entry_source.lines().map(|line| line.to_string()).collect::<Vec<String>>()
+    });
+
+    let script_names: Vec<String> = match &stdin_entry {
+        Some(_) => vec![selection.name.clone()],
+        None => selection.scripts.iter().map(|s| s.trim().to_string()).collect(),
+    };
+
+    let mut output = BatchOutput {
+        files: Vec::new(),
+        has_violations: false,
+        failed: Vec::new(),
+        total: script_names.len(),
+        graphs: Vec::new(),
+        sizes: Vec::new(),
+        size_record: None,
+    };
+    let mut warning_groups = diagnostics::WarningGroups::default();
+    let mut size_record = match options.max_growth {
+        Some(_) => sizes::read_record(),
+        None => BTreeMap::new(),
+    };
+
+    let jobs = options
+        .jobs
+        .unwrap_or_else(|| std::thread::available_parallelism().map_or(1, |parallelism| parallelism.get()))
+        .max(1);
+    let mut resolved_scripts = Vec::new();
+
+    for batch in script_names.chunks(jobs) {
+        resolved_scripts.extend(std::thread::scope(|scope| {
+            let handles: Vec<_> = batch
+                .iter()
+                .map(|script_name| scope.spawn(|| resolve_script(source, selection, script_name, stdin_entry.as_ref(), options)))
+                .collect();
+
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap_or_else(|_| Err(String::from("bundling panicked"))))
+                .collect::<Vec<_>>()
+        }));
+
+        if options.fail_fast && resolved_scripts.iter().any(|resolved| resolved.is_err()) {
+            break;
+        }
+    }
+
+    for (script_name, resolved) in script_names.iter().zip(resolved_scripts) {
+        let ResolvedBundle { lines: bundled_lines, warnings, imports } = match resolved {
+            Ok(resolved) => resolved,
+            Err(err) => {
+                eprintln!("error: {}.py: {}", script_name, err);
+
+                if options.fail_fast {
+                    std::process::exit(1);
+                }
+
+                output.failed.push(script_name.clone());
+                continue;
+            }
+        };
+
+        let mut bundled_output_lines: Vec<String> = bundled_lines.iter().map(|line| line.text.to_string()).collect();
+
+        for warning in &warnings {
+            warning_groups.add(script_name, warning);
+        }
+
+        if let Some(mode) = &desktop_mode {
+            bundled_output_lines = desktop::apply_mode(bundled_output_lines, mode);
+        }
+
+        if options.with_shims {
+            bundled_output_lines = shims::inject_shims(bundled_output_lines);
+        }
+
+        if options.tree_shake {
+            let line_count = bundled_output_lines.len();
+            let elimination = dead_code::eliminate_dead_code(bundled_output_lines);
+
+            if !options.quiet && !elimination.removed.is_empty() {
+                eprintln!(
+                    "{}.py: tree-shaking removed {} ({} lines)",
+                    script_name,
+                    elimination.removed.join(", "),
+                    line_count - elimination.lines.len()
+                );
+            }
+
+            bundled_output_lines = elimination.lines;
+        }
+
+        if let Some(rules) = rules {
+            for violation in rules::check_rules(&bundled_output_lines, rules) {
+                output.has_violations = true;
+                eprintln!(
+                    "{}.py:{}: [{}] {}: {}",
+                    script_name, violation.line_number, violation.rule_id, violation.message, violation.line
+                );
+            }
+        }
+
+        if let Some(profile) = target_profile {
+            let mapping = timing::map_time_api(bundled_output_lines.clone(), profile);
+            add_located_warnings(&mut warning_groups, script_name, &bundled_lines, &bundled_output_lines, &mapping.warnings);
+            bundled_output_lines = mapping.lines;
+
+            let report = subset::check_subset(&bundled_output_lines, profile);
+
+            for violation in report.violations {
+                output.has_violations = true;
+                eprintln!(
+                    "{}.py:{}: [{}] {}: {}",
+                    script_name, violation.line_number, violation.rule_id, violation.message, violation.line
+                );
+            }
+
+            add_located_warnings(&mut warning_groups, script_name, &bundled_lines, &bundled_output_lines, &report.warnings);
+
+            let display = display::check_display(bundled_output_lines.clone(), profile, options.wrap_text);
+            add_located_warnings(&mut warning_groups, script_name, &bundled_lines, &bundled_output_lines, &display.warnings);
+            bundled_output_lines = display.lines;
+
+            if options.paginate {
+                let pagination = paging::paginate(bundled_output_lines, profile);
+                bundled_output_lines = pagination.lines;
+                add_located_warnings(&mut warning_groups, script_name, &bundled_lines, &bundled_output_lines, &pagination.warnings);
+            }
+        }
+
+        if options.perf_lint {
+            let findings: Vec<String> = perf::lint_performance(&bundled_output_lines)
+                .into_iter()
+                .map(|finding| format!("{}: [{}] {}: {}", finding.line_number, finding.rule_id, finding.message, finding.line))
+                .collect();
+            add_located_warnings(&mut warning_groups, script_name, &bundled_lines, &bundled_output_lines, &findings);
+        }
+
+        let over_budget: Vec<String> = frames::estimate_frames(&bundled_output_lines)
+            .iter()
+            .filter(|estimate| estimate.calls > options.frame_budget)
+            .map(|estimate| {
+                format!(
+                    "{}: loop makes {}{} drawing calls per frame, over the budget of {}; redraw only what changed to keep it smooth",
+                    estimate.line_number,
+                    if estimate.lower_bound { "at least " } else { "about " },
+                    estimate.calls,
+                    options.frame_budget
+                )
+            })
+            .collect();
+        add_located_warnings(&mut warning_groups, script_name, &bundled_lines, &bundled_output_lines, &over_budget);
+
+        let unsupported_imports = modules::find_unsupported_imports(&bundled_output_lines);
+
+        if !unsupported_imports.is_empty() {
+            let origins = provenance::align_origins(&bundled_lines, &bundled_output_lines);
+
+            for unsupported in unsupported_imports {
+                output.has_violations = true;
+                let location = match origins.get(unsupported.line_number - 1).and_then(|origin| origin.as_ref()) {
+                    Some(origin) => format!("{}:{}", origin.source, origin.line_number),
+                    None => String::from("<generated>"),
+                };
+                eprintln!(
+                    "{}.py:{}: ({}) module '{}' is not available on the calculator: {}",
+                    script_name, unsupported.line_number, location, unsupported.module, unsupported.line
+                );
+            }
+        }
+
+        if options.minify {
+            bundled_output_lines = minify::minify(bundled_output_lines);
+        }
+
+        if options.mangle {
+            bundled_output_lines = mangle::mangle(bundled_output_lines);
+        }
+
+        if let Some(identifier) = &options.watermark {
+            bundled_output_lines = watermark::embed_watermark(bundled_output_lines, identifier);
+        }
+
+        match preview {
+            _ if options.quiet => {}
+            Preview::Annotated => {
+                let origins = provenance::align_origins(&bundled_lines, &bundled_output_lines);
+                for annotated_line in provenance::annotate(&bundled_output_lines, &origins) {
+                    eprintln!("{}", annotated_line);
+                }
             }
+            Preview::Plain => {
+                for demo_line in &bundled_output_lines {
+                    eprintln!("{}", demo_line);
+                }
+            }
+            Preview::None => {}
+        }
+
+        let size = sizes::ScriptSize::measure(script_name, &bundled_lines, &bundled_output_lines);
+
+        if size.bytes > options.memory_limit {
+            warning_groups.add(
+                script_name,
+                &format!("{}.py is {} bytes, over the {}-byte memory limit", script_name, size.bytes, options.memory_limit),
+            );
         }
 
-        if capture {
-            output_lines.push(line.clone());
-            let current_indent = line.find(|c: char| !c.is_whitespace()).unwrap_or(0);
-            if indent_level.is_some() && current_indent <= indent_level.unwrap() && line.trim().is_empty() {
-                capture = false;
+        if let Some(max_growth) = options.max_growth {
+            let record_name = format!("{}/{}", selection.group, script_name);
+
+            match size_record.get(&record_name) {
+                Some(&previous) if max_growth.exceeded(previous, size.bytes) => {
+                    output.has_violations = true;
+                    eprintln!(
+                        "{}.py grew from {} to {} bytes since the last recorded build, more than --max-growth {}",
+                        script_name, previous, size.bytes, max_growth
+                    );
+                }
+                _ => {
+                    size_record.insert(record_name, size.bytes);
+                }
             }
         }
+
+        output.sizes.push(size);
+        output.graphs.push(ScriptGraph::new(script_name, &bundled_lines, &bundled_output_lines, imports));
+
+        output.files.push(FileObject {
+            script_name: script_name.to_string(),
+            contents: bundled_output_lines,
+        })
+    }
+
+    if !options.quiet {
+        for warning in warning_groups.render(script_names.len()) {
+            eprintln!("{}", warning);
+        }
+    }
+
+    output.files.sort_by(|first, second| first.script_name.cmp(&second.script_name));
+    output.graphs.sort_by(|first, second| first.script_name.cmp(&second.script_name));
+    output.sizes.sort_by(|first, second| first.script_name.cmp(&second.script_name));
+
+    if options.max_growth.is_some() && !output.has_violations {
+        output.size_record = Some(size_record);
+    }
+
+    match options.size_report.as_deref() {
+        Some("json") => eprintln!("{}", sizes::render_json(&output.sizes)),
+        Some(_) => eprintln!("{}", sizes::render_text(&output.sizes)),
+        None => {}
     }
 
-    output_lines
+    output
 }
 
-fn bundle_script_import_lines(_line: &String, paths: &PathsMap) -> Vec<String> {
-    let mut output_lines = Vec::new();
-    let file = fetch_file_content(&paths.script);
+fn add_located_warnings(warning_groups: &mut diagnostics::WarningGroups, script_name: &str, bundled_lines: &[BundledLine], output_lines: &[String], warnings: &[String]) {
+    if warnings.is_empty() {
+        return;
+    }
 
-    for script_line in file {
+    let origins = provenance::align_origins(bundled_lines, output_lines);
 
-        if script_line.contains("common.helpers") {
-            let helper_lines = bundle_common_import_lines(&script_line, &paths.common_helpers);
-            output_lines.extend(helper_lines);
-        } else if script_line.contains("from") && script_line.contains("import") {
-            if let Some(adjacent_path) = resolve_adjacent_script_path(&script_line, paths) {
-                let adjacent_lines = bundle_adjacent_script_import_lines(&script_line, &adjacent_path);
-                output_lines.extend(adjacent_lines);
-            }
+    for warning in warnings {
+        let origin = warning
+            .split_once(": ")
+            .and_then(|(line_number, message)| Some((line_number.parse::<usize>().ok()?.checked_sub(1)?, message)))
+            .and_then(|(index, message)| Some((origins.get(index)?.as_ref()?, message)));
+
+        let located = match origin {
+            Some((origin, message)) => format!("{}:{}: {}", origin.source, origin.line_number, message),
+            None => format!("{}.py:{}", script_name, warning),
+        };
+
+        warning_groups.add(script_name, &located);
+    }
+}
+
+fn print_failures(output: &BatchOutput) -> bool {
+    if output.failed.is_empty() {
+        return false;
+    }
+
+    eprintln!("failed: {} of {} scripts ({})", output.failed.len(), output.total, output.failed.join(", "));
+    true
+}
+
+fn report_failures(output: &BatchOutput) {
+    if print_failures(output) {
+        std::process::exit(1);
+    }
+}
+
+fn output_path(path: &Path, args: &BundleArgs, artifacts: &[sink::Artifact]) -> PathBuf {
+    if args.unpacked {
+        return path.to_path_buf();
+    }
+
+    let zip_path = if path.is_dir() {
+        path.join(format!("{}.zip", args.selection.group.replace('/', "_")))
+    } else {
+        path.to_path_buf()
+    };
+
+    if !args.hash_names {
+        return zip_path;
+    }
+
+    let file_name = zip_path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+    zip_path.with_file_name(sink::with_hash_suffix(&file_name, &sink::content_hash(artifacts)))
+}
+
+fn reject_shims(options: &BuildOptions, target: &str) {
+    if options.with_shims {
+        eprintln!("--with-shims stubs out calculator modules for desktop Python and cannot be used for {}", target);
+        std::process::exit(1);
+    }
+}
+
+fn run_bundle(source: &dyn Source, args: &BundleArgs) {
+    if args.format == "8xv" {
+        reject_shims(&args.build, ".8xv AppVars");
+    }
+
+    let sinks = sink::SinkRegistry::with_builtin_sinks();
+
+    let registered_sink = match &args.output {
+        Some(_) => None,
+        None => Some(sinks.get(&args.sink).unwrap_or_else(|| {
+            eprintln!("Unknown output sink: {} (available: {})", args.sink, sinks.names().join(", "));
+            std::process::exit(1);
+        })),
+    };
+
+    let preview = if args.annotate {
+        Preview::Annotated
+    } else if args.dev {
+        Preview::Plain
+    } else {
+        Preview::None
+    };
+
+    if args.watch {
+        watch_bundles(source, args, registered_sink, preview);
+    }
+
+    let mut output = build_scripts(source, &args.selection, &args.build, preview);
+
+    if let Some(format) = args.graph.as_deref() {
+        match format {
+            "json" => println!("{}", graph::render_json(&output.graphs)),
+            _ => println!("{}", graph::render_dot(&output.graphs)),
+        }
+
+        report_failures(&output);
+        return;
+    }
+
+    if output.has_violations {
+        std::process::exit(1);
+    }
+
+    if let Err(err) = emit_bundles(source, args, std::mem::take(&mut output.files), registered_sink, output.size_record.as_ref()) {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
+
+    report_failures(&output);
+}
+
+fn emit_bundles(source: &dyn Source, args: &BundleArgs, files: Vec<FileObject>, registered_sink: Option<&dyn sink::OutputSink>, size_record: Option<&BTreeMap<String, usize>>) -> Result<(), String> {
+    let line_counts: Vec<usize> = files.iter().map(|file| file.contents.len()).collect();
+    let source_bytes: usize = files.iter().map(|file| file.contents.join("\n").len()).sum();
+
+    let metadata = sink::EntryMetadata {
+        permissions: args.entry_mode,
+        modified: args.entry_mtime,
+        comment: args.entry_comment.clone(),
+    };
+
+    let mut artifacts = sink::encode_artifacts(files, &args.format, &metadata)?;
+
+    if args.hash_names {
+        sink::hash_artifact_names(&mut artifacts);
+    }
+
+    let revision = env::var("SOURCE_REVISION").ok().or_else(|| source.revision());
+    sink::record_provenance(&mut artifacts, revision.as_deref());
+
+    let output_path = args.output.as_ref().map(|path| output_path(path, args, &artifacts));
+
+    let file_sink: Option<Box<dyn sink::OutputSink>> = output_path.clone().map(|path| -> Box<dyn sink::OutputSink> {
+        if args.unpacked {
+            Box::new(sink::DirectorySink { path })
         } else {
-            output_lines.push(script_line);
+            Box::new(sink::ZipFileSink { path })
+        }
+    });
+
+    if args.dry_run {
+        let destination = match &output_path {
+            Some(path) => format!("to {}", path.display()),
+            None => format!("via the {} sink", args.sink),
+        };
+
+        for (artifact, line_count) in artifacts.iter().zip(&line_counts) {
+            eprintln!("would emit {} ({} lines, {} bytes) {}", artifact.file_name, line_count, artifact.bytes.len(), destination);
         }
+
+        let zip_content = sink::create_zip(artifacts)?;
+        eprintln!("total: {} bytes of source, {} byte archive", source_bytes, zip_content.len());
+
+        return Ok(());
+    }
+
+    let output_sink = file_sink.as_deref().or(registered_sink).expect("either --output or a registered sink is selected");
+
+    if !artifacts.is_empty() {
+        output_sink.emit(artifacts)?;
+    }
+
+    if let Some(size_record) = size_record {
+        sizes::write_record(size_record);
     }
 
-    output_lines
+    Ok(())
 }
 
-fn resolve_adjacent_script_path(line: &str, paths: &PathsMap) -> Option<String> {
-    let import_section = line.split_whitespace().nth(1)?;
+fn watch_scripts(source: &dyn Source, selection: &ScriptSelection, options: &BuildOptions, preview: Preview, mut rebuilt: impl FnMut(BatchOutput)) -> ! {
+    let script_names: Vec<String> = selection.scripts.iter().map(|s| s.trim().to_string()).collect();
+    let mut changed = script_names.clone();
+    let mut fingerprints = watch::fingerprints(source, &watch::watched_scripts(source, &selection.group, &script_names));
 
-    let mut parts = import_section.split('.');
+    loop {
+        if !changed.is_empty() {
+            let changed_selection = ScriptSelection {
+                group: selection.group.clone(),
+                scripts: changed,
+                stdin: false,
+                name: selection.name.clone(),
+            };
 
-    let group_name = parts.next()?;
-    let script_name = parts.next()?;
-    let file_name = parts.next()?;
+            rebuilt(build_scripts(source, &changed_selection, options, preview));
+            eprintln!("watching {} for changes", selection.group);
+        }
+
+        std::thread::sleep(watch::POLL_INTERVAL);
 
-    Some(format!("{}/{}/{}/{}.py", paths.project, group_name, script_name, file_name))
+        let watched = watch::watched_scripts(source, &selection.group, &script_names);
+        let latest = watch::fingerprints(source, &watched);
+        changed = watch::changed_scripts(&watched, &fingerprints, &latest);
+        fingerprints = latest;
+    }
 }
 
-fn bundle_adjacent_script_import_lines(_line: &String, script_path: &String) -> Vec<String> {
-    let mut output_lines = Vec::new();
-    let file = fetch_file_content(script_path);
+fn watch_bundles(source: &dyn Source, args: &BundleArgs, registered_sink: Option<&dyn sink::OutputSink>, preview: Preview) -> ! {
+    let mut bundles: BTreeMap<String, FileObject> = BTreeMap::new();
+
+    watch_scripts(source, &args.selection, &args.build, preview, |mut output| {
+        if !output.has_violations {
+            for file in std::mem::take(&mut output.files) {
+                bundles.insert(file.script_name.clone(), file);
+            }
+
+            if let Err(err) = emit_bundles(source, args, bundles.values().cloned().collect(), registered_sink, output.size_record.as_ref()) {
+                eprintln!("{}", err);
+            }
 
-    for script_line in file {
+            if args.build.size_report.is_none() {
+                eprintln!("{}", sizes::render_text(&output.sizes));
+            }
+        }
+
+        print_failures(&output);
+    })
+}
 
-        if script_line.contains("common.helpers") {
-            let helper_lines = bundle_common_import_lines(&script_line, script_path);
-            output_lines.extend(helper_lines);
+fn check_selection(source: &dyn Source, selection: &ScriptSelection, options: &BuildOptions) -> bool {
+    let output = build_scripts(source, selection, options, Preview::None);
+
+    if output.has_violations {
+        return false;
+    }
+
+    if !options.quiet {
+        for file in &output.files {
+            eprintln!("ok: {}.py ({} lines)", file.script_name, file.contents.len());
         }
+    }
+
+    !print_failures(&output)
+}
+
+fn root_relative(root: &str, path: &Path) -> String {
+    let relative = std::fs::canonicalize(path)
+        .ok()
+        .zip(std::fs::canonicalize(root).ok())
+        .and_then(|(path, root)| Some(path.strip_prefix(root).ok()?.to_path_buf()))
+        .unwrap_or_else(|| path.to_path_buf());
+
+    relative.to_string_lossy().replace('\\', "/").trim_start_matches("./").to_string()
+}
+
+fn affected_selections(source: &dyn Source, changed: &[PathBuf], max_import_depth: usize) -> Vec<ScriptSelection> {
+    let changed: Vec<String> = changed.iter().map(|path| root_relative(source.root(), path)).collect();
+    let mut affected: BTreeMap<String, Vec<String>> = BTreeMap::new();
+
+    for (script, resolved) in resolve_repository(source, max_import_depth) {
+        let script_directory = format!("{}/", script);
+
+        let is_affected = match &resolved {
+            Ok(bundle) => {
+                let files: HashSet<&str> = bundle
+                    .lines
+                    .iter()
+                    .filter_map(|line| line.origin.as_ref().map(|origin| &*origin.source))
+                    .chain(bundle.imports.iter().flat_map(|edge| [edge.importer.as_str(), edge.imported.as_str()]))
+                    .collect();
 
-        output_lines.push(script_line);
+                changed.iter().any(|path| path.starts_with(&script_directory) || files.contains(path.as_str()))
+            }
+            Err(err) => changed.iter().any(|path| path.starts_with(&script_directory) || err.contains(path.as_str())),
+        };
+
+        if let (true, Some((group, script_name))) = (is_affected, script.rsplit_once('/')) {
+            affected.entry(group.to_string()).or_default().push(script_name.to_string());
+        }
     }
 
-    output_lines
+    affected
+        .into_iter()
+        .map(|(group, scripts)| ScriptSelection { group, scripts, stdin: false, name: String::from("stdin") })
+        .collect()
 }
 
-fn create_zip(files: Vec<FileObject>) -> Vec<u8> {
-    let mut buffer = Cursor::new(Vec::new());
+fn run_check(source: &dyn Source, args: &CheckArgs) {
+    if !args.changed.is_empty() {
+        let selections = affected_selections(source, &args.changed, args.build.max_import_depth);
+
+        if selections.is_empty() {
+            if !args.build.quiet {
+                eprintln!("ok: no scripts include the changed files");
+            }
+            return;
+        }
 
-    let mut zip = ZipWriter::new(&mut buffer);
+        let mut passed = true;
+        for selection in &selections {
+            passed &= check_selection(source, selection, &args.build);
+        }
 
-    let options: FileOptions<()> = FileOptions::default()
-        .compression_method(zip::CompressionMethod::Stored)
-        .unix_permissions(0o755);
+        if !passed {
+            std::process::exit(1);
+        }
+        return;
+    }
 
-    for file in files {
-        zip.start_file(format!("{}.py", file.script_name.as_str()), options).unwrap();
+    let Some(selection) = args.selection() else {
+        eprintln!("check needs --group and --script, --stdin, or --changed");
+        std::process::exit(1);
+    };
 
-        let file_contents = file.contents.join("\n");
-        zip.write_all(file_contents.as_bytes()).unwrap();
+    if !check_selection(source, &selection, &args.build) {
+        std::process::exit(1);
     }
+}
 
-    zip.finish().unwrap();
+fn directory_entries(source: &dyn Source, group: Option<&str>) -> Result<Vec<String>, String> {
+    let directory = match group {
+        Some(group) => format!("{}/{}", source.root(), group),
+        None => source.root().to_string(),
+    };
 
-    buffer.into_inner()
+    source.check_within_root(&directory)?;
+
+    let entries = source
+        .list_directory(&directory)
+        .ok_or_else(|| format!("Cannot list {}: the source does not provide a directory listing", directory))?;
+
+    Ok(entries
+        .into_iter()
+        .filter(|entry| !entry.contains('.') && (group.is_some() || entry != "common"))
+        .collect())
 }
 
-fn main() {
-    dotenv().ok();
+fn list_entries(source: &dyn Source, group: Option<&str>) -> Vec<String> {
+    directory_entries(source, group).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    })
+}
 
-    let args = gather_args();
+fn repository_selections(source: &dyn Source) -> Vec<ScriptSelection> {
+    list_entries(source, None)
+        .into_iter()
+        .filter_map(|group| {
+            let scripts = directory_entries(source, Some(&group)).ok()?;
+            (!scripts.is_empty()).then(|| ScriptSelection { group, scripts, stdin: false, name: String::from("stdin") })
+        })
+        .collect()
+}
 
-    let mut files = Vec::new();
+fn run_list(source: &dyn Source, group: Option<&str>) {
+    for entry in list_entries(source, group) {
+        println!("{}", entry);
+    }
+}
 
-    for script_name in args[2].split(',').map(|s| s.trim()) {
-        let paths = describe_paths(&args[1], &script_name.to_string());
+fn run_pack(source: &dyn Source, args: &PackArgs) {
+    reject_shims(&args.build, "pack, which ships .8xv AppVars");
 
-        let bundled_output_lines: Vec<String> = build_bundle(&paths);
+    let scripts = list_entries(source, Some(&args.group));
 
-        if args.len() == 4 && args[3] == "DEV" {
-            for demo_line in &bundled_output_lines {
-                println!("{}", demo_line);
-            }
+    if scripts.iter().any(|script| script == pack::LAUNCHER_NAME) {
+        eprintln!("Cannot pack {}: a script is named {}, which the generated launcher needs", args.group, pack::LAUNCHER_NAME);
+        std::process::exit(1);
+    }
+
+    let selection = ScriptSelection {
+        group: args.group.clone(),
+        scripts,
+        stdin: false,
+        name: String::from("stdin"),
+    };
+
+    let output = build_scripts(source, &selection, &args.build, Preview::None);
+
+    if output.has_violations {
+        std::process::exit(1);
+    }
+
+    if output.files.is_empty() {
+        report_failures(&output);
+        eprintln!("Cannot pack {}: the group has no scripts", args.group);
+        std::process::exit(1);
+    }
+
+    let script_names: Vec<String> = output.files.iter().map(|file| file.script_name.clone()).collect();
+    let appvar_names = pack::appvar_names(&script_names);
+
+    let mut files = output.files.clone();
+    files.push(pack::launcher(&script_names));
+
+    let mut appvar_files: Vec<FileObject> = output
+        .files
+        .iter()
+        .zip(&appvar_names)
+        .map(|(file, appvar_name)| FileObject { script_name: appvar_name.clone(), contents: file.contents.clone() })
+        .collect();
+    appvar_files.push(FileObject { script_name: pack::LAUNCHER_NAME.to_ascii_uppercase(), ..pack::launcher(&appvar_names) });
+
+    let revision = env::var("SOURCE_REVISION").ok().or_else(|| source.revision());
+    let readme = pack::readme(&args.group, &output.files, &appvar_names, revision.as_deref());
+    let metadata_json = pack::metadata_json(&args.group, &output.files, &appvar_names, revision.as_deref());
+
+    let metadata = sink::EntryMetadata {
+        permissions: 0o644,
+        modified: args.entry_mtime,
+        comment: None,
+    };
+
+    let documents = || {
+        vec![
+            sink::Artifact { file_name: String::from("README.md"), bytes: readme.clone().into_bytes(), metadata: metadata.clone() },
+            sink::Artifact { file_name: String::from("pack.json"), bytes: metadata_json.clone().into_bytes(), metadata: metadata.clone() },
+        ]
+    };
+
+    let encode = |files: Vec<FileObject>, format: &str| {
+        let mut artifacts = sink::encode_artifacts(files, format, &metadata).unwrap_or_else(|err| {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        });
+        sink::record_provenance(&mut artifacts, revision.as_deref());
+        artifacts
+    };
+
+    let mut py_artifacts = encode(files, "py");
+    py_artifacts.extend(documents());
+    let appvar_artifacts = encode(appvar_files, "8xv");
+
+    let directory = args.output.clone().unwrap_or_else(|| PathBuf::from(format!("{}-pack", args.group.replace('/', "_"))));
+
+    let sinks: Vec<(Box<dyn sink::OutputSink>, Vec<sink::Artifact>)> = vec![
+        (Box::new(sink::DirectorySink { path: directory.clone() }), documents()),
+        (Box::new(sink::ZipFileSink { path: directory.join(format!("{}.zip", args.group.replace('/', "_"))) }), py_artifacts),
+        (Box::new(sink::DirectorySink { path: directory.join("8xv") }), appvar_artifacts),
+    ];
+
+    for (output_sink, artifacts) in sinks {
+        if let Err(err) = output_sink.emit(artifacts) {
+            eprintln!("{}", err);
+            std::process::exit(1);
         }
+    }
 
-        files.push(FileObject {
-            script_name: script_name.to_string(),
-            contents: bundled_output_lines,
+    if let Some(size_record) = &output.size_record {
+        sizes::write_record(size_record);
+    }
+
+    report_failures(&output);
+}
+
+fn resolve_repository(source: &dyn Source, max_import_depth: usize) -> Vec<(String, Result<ResolvedBundle, String>)> {
+    repository_selections(source)
+        .into_iter()
+        .flat_map(|selection| {
+            selection
+                .scripts
+                .iter()
+                .map(|script_name| {
+                    let resolved = describe_paths(source, &selection.group, script_name).and_then(|paths| build_bundle(source, &paths, None, max_import_depth));
+                    (format!("{}/{}", selection.group, script_name), resolved)
+                })
+                .collect::<Vec<_>>()
         })
+        .collect()
+}
+
+fn run_check_helpers(source: &dyn Source, max_import_depth: usize) {
+    let helpers_path = format!("{}/common/helpers.py", source.root());
+    let helpers_label = relative_source(&helpers_path, source.root()).to_string();
+
+    let helpers = source.read_lines(&helpers_path).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    });
+    let defined: Vec<String> = scoping::module_level_names(&helpers).into_iter().collect();
+
+    let resolved = resolve_repository(source, max_import_depth);
+    let mut missing: BTreeMap<&str, BTreeSet<String>> = BTreeMap::new();
+    let mut broken = BTreeSet::new();
+
+    for (script, resolved) in &resolved {
+        let bundle = match resolved {
+            Ok(bundle) => bundle,
+            Err(err) => {
+                eprintln!("error: {}: {}", script, err);
+                broken.insert(script);
+                continue;
+            }
+        };
+
+        for edge in bundle.imports.iter().filter(|edge| edge.imported == helpers_label) {
+            for symbol in edge.symbols.iter().filter(|symbol| !defined.contains(*symbol)) {
+                missing.entry(symbol).or_default().insert(format!("{} (imported in {})", script, edge.importer));
+                broken.insert(script);
+            }
+        }
+    }
+
+    for (symbol, users) in &missing {
+        let suggestion = suggest::closest_match(symbol, &defined).map(|name| format!("; did you mean {}?", name)).unwrap_or_default();
+        eprintln!("{} does not define {}, which {} script import(s) use{}", helpers_label, symbol, users.len(), suggestion);
+
+        for user in users {
+            eprintln!("  {}", user);
+        }
+    }
+
+    if !broken.is_empty() {
+        eprintln!("broken: {} of {} scripts", broken.len(), resolved.len());
+        std::process::exit(1);
+    }
+
+    eprintln!("ok: all {} scripts resolve against {}", resolved.len(), helpers_label);
+}
+
+fn run_who_uses(source: &dyn Source, symbol: &str, module: &str, max_import_depth: usize) {
+    let module_label = format!("{}.py", module.replace('.', "/"));
+    let mut users = Vec::new();
+
+    for (script, resolved) in resolve_repository(source, max_import_depth) {
+        let bundle = match resolved {
+            Ok(bundle) => bundle,
+            Err(err) => {
+                eprintln!("warning: skipped {}: {}", script, err);
+                continue;
+            }
+        };
+
+        for edge in bundle.imports.iter().filter(|edge| edge.imported == module_label && edge.symbols.iter().any(|name| name == symbol)) {
+            let user = format!("{}: {}:{}", script, edge.importer, edge.line_number);
+            if !users.contains(&user) {
+                users.push(user);
+            }
+        }
+    }
+
+    if users.is_empty() {
+        eprintln!("no script imports {} from {}", symbol, module);
+        return;
+    }
+
+    for user in users {
+        println!("{}", user);
+    }
+}
+
+fn run_dead_helpers(source: &dyn Source, max_import_depth: usize) {
+    let helpers_path = format!("{}/common/helpers.py", source.root());
+    let helpers_label = relative_source(&helpers_path, source.root()).to_string();
+
+    let helpers = source.read_lines(&helpers_path).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    });
+    let statements = python::parse_statements(&helpers, &helpers_label).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    });
+
+    let mut inlined: HashSet<usize> = HashSet::new();
+    let mut skipped = 0;
+
+    for (script, resolved) in resolve_repository(source, max_import_depth) {
+        match resolved {
+            Ok(bundle) => inlined.extend(
+                bundle
+                    .lines
+                    .iter()
+                    .filter_map(|line| line.origin.as_ref())
+                    .filter(|origin| *origin.source == *helpers_label)
+                    .map(|origin| origin.line_number),
+            ),
+            Err(err) => {
+                eprintln!("warning: skipped {}: {}", script, err);
+                skipped += 1;
+            }
+        }
+    }
+
+    let helpers: Vec<&Statement> = statements
+        .iter()
+        .filter(|statement| matches!(statement.kind, StatementKind::Definition(_) | StatementKind::Assignment(_)))
+        .collect();
+    let mut used: Vec<bool> = helpers
+        .iter()
+        .map(|statement| (statement.first_line + 1..=statement.last_line + 1).any(|line_number| inlined.contains(&line_number)))
+        .collect();
+
+    loop {
+        let referenced: HashSet<&str> = helpers
+            .iter()
+            .zip(&used)
+            .filter(|(_, used)| **used)
+            .flat_map(|(statement, _)| statement.references.iter().map(|name| name.as_str()))
+            .collect();
+        let newly_used: Vec<usize> = (0..helpers.len())
+            .filter(|index| !used[*index] && helpers[*index].bound_names().iter().any(|name| referenced.contains(name)))
+            .collect();
+
+        if newly_used.is_empty() {
+            break;
+        }
+        newly_used.into_iter().for_each(|index| used[index] = true);
     }
 
-    let zip_content = create_zip(files);
+    let unused: Vec<&&Statement> = helpers.iter().zip(&used).filter(|(_, used)| !**used).map(|(statement, _)| statement).collect();
+
+    for statement in &unused {
+        println!("{}:{}: {}", helpers_label, statement.first_line + 1, statement.bound_names().join(", "));
+    }
+
+    eprintln!("{} of {} helpers in {} are not used by any script", unused.len(), helpers.len(), helpers_label);
+
+    if skipped > 0 {
+        eprintln!("warning: {} script(s) failed to resolve, so helpers only they use are listed as unused", skipped);
+    }
+}
+
+fn run_snapshot(source: &dyn Source, args: &SnapshotArgs) {
+    let snapshot_directory = std::fs::canonicalize(&args.directory).ok();
+    let mut bundles: BTreeMap<PathBuf, String> = BTreeMap::new();
+    let mut attempted: HashSet<PathBuf> = HashSet::new();
+    let mut failed = false;
+
+    for selection in repository_selections(source) {
+        let group_directory = Path::new(source.root()).join(&selection.group);
+        if snapshot_directory.is_some() && std::fs::canonicalize(&group_directory).ok() == snapshot_directory {
+            continue;
+        }
+
+        let output = build_scripts(source, &selection, &args.build, Preview::None);
+        failed |= output.has_violations | print_failures(&output);
+
+        attempted.extend(selection.scripts.iter().map(|script| Path::new(&selection.group).join(format!("{}.py", script))));
+        bundles.extend(output.files.iter().map(|file| (Path::new(&selection.group).join(format!("{}.py", file.script_name)), file.contents.join("\n"))));
+    }
+
+    let snapshots = snapshot::read_snapshots(&args.directory);
+    let stale: Vec<&PathBuf> = snapshots.keys().filter(|path| !attempted.contains(*path)).collect();
+
+    if args.update {
+        for (path, contents) in &bundles {
+            if snapshots.get(path) == Some(contents) {
+                continue;
+            }
 
-    println!("{}", general_purpose::STANDARD.encode(&zip_content));
+            let snapshot_path = args.directory.join(path);
+            if let Err(err) = snapshot::write_snapshot(&snapshot_path, contents) {
+                eprintln!("{}", err);
+                std::process::exit(1);
+            }
+            eprintln!("wrote {}", snapshot_path.display());
+        }
+
+        for path in stale {
+            let snapshot_path = args.directory.join(path);
+            if let Err(err) = std::fs::remove_file(&snapshot_path) {
+                eprintln!("Failed to remove {}: {}", snapshot_path.display(), err);
+                std::process::exit(1);
+            }
+            eprintln!("removed {}", snapshot_path.display());
+        }
+    } else {
+        let mut differences = stale.len();
+
+        for (path, contents) in &bundles {
+            let snapshot_path = args.directory.join(path);
+
+            match snapshots.get(path) {
+                None => {
+                    eprintln!("new: {} has no snapshot", snapshot_path.display());
+                    differences += 1;
+                }
+                Some(snapshot) => {
+                    if let Some((line_number, expected, actual)) = snapshot::first_difference(snapshot, contents) {
+                        eprintln!("changed: {}:{}\n  - {}\n  + {}", snapshot_path.display(), line_number, expected, actual);
+                        differences += 1;
+                    }
+                }
+            }
+        }
+
+        for path in stale {
+            eprintln!("stale: {} is no longer built", args.directory.join(path).display());
+        }
+
+        if differences > 0 {
+            eprintln!("{} snapshot(s) differ; run `snapshot --update` to accept the new bundles", differences);
+            std::process::exit(1);
+        }
+
+        eprintln!("ok: {} snapshot(s) match", bundles.len());
+    }
+
+    if failed {
+        std::process::exit(1);
+    }
+}
+
+fn write_appvars(folder: &Path, files: &[FileObject]) -> Result<Vec<PathBuf>, String> {
+    let metadata = sink::EntryMetadata {
+        permissions: 0o644,
+        modified: None,
+        comment: None,
+    };
+
+    let artifacts = sink::encode_artifacts(files.to_vec(), "8xv", &metadata)?;
+    let paths = artifacts.iter().map(|artifact| folder.join(&artifact.file_name)).collect();
+
+    (sink::DirectorySink { path: folder.to_path_buf() }).emit(artifacts)?;
+
+    Ok(paths)
+}
+
+fn open_in_ti_connect(folder: &Path, files: &[PathBuf]) -> Result<(), String> {
+    let Some(program) = transfer::ti_connect_path() else {
+        eprintln!("send the files in {} to the calculator manually", folder.display());
+        return Ok(());
+    };
+
+    transfer::open_with_files(&program, files)?;
+    eprintln!("opened {} with {} file(s) from {}", program.display(), files.len(), folder.display());
+
+    Ok(())
+}
+
+fn run_transfer(source: &dyn Source, args: &TransferArgs) {
+    reject_shims(&args.build, "transfer to a calculator");
+
+    let folder = args
+        .folder
+        .clone()
+        .unwrap_or_else(|| env::temp_dir().join(format!("ti-84-transfer-{}", args.selection.group.replace('/', "_"))));
+
+    if args.watch {
+        watch_scripts(source, &args.selection, &args.build, Preview::None, |output| {
+            if !output.has_violations && !output.files.is_empty() {
+                let sent = write_appvars(&folder, &output.files).and_then(|files| {
+                    if let Some(size_record) = &output.size_record {
+                        sizes::write_record(size_record);
+                    }
+                    open_in_ti_connect(&folder, &files)
+                });
+
+                if let Err(err) = sent {
+                    eprintln!("{}", err);
+                }
+            }
+
+            print_failures(&output);
+        });
+    }
+
+    let output = build_scripts(source, &args.selection, &args.build, Preview::None);
+
+    if output.has_violations {
+        std::process::exit(1);
+    }
+
+    let files = write_appvars(&folder, &output.files).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    });
+
+    if let Some(size_record) = &output.size_record {
+        sizes::write_record(size_record);
+    }
+
+    if let Err(err) = open_in_ti_connect(&folder, &files) {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
+
+    report_failures(&output);
+}
+
+fn report_missing_tools(command: &Command, root_directory: &str) {
+    let missing = match command {
+        Command::Bundle(args) if !args.build.quiet => {
+            let mut missing = source::missing_tools(root_directory);
+            if args.output.is_none() && args.sink == "clipboard" {
+                missing.extend(clipboard::missing_tools());
+            }
+            missing
+        }
+        Command::Pack(args) if !args.build.quiet => source::missing_tools(root_directory),
+        Command::Transfer(args) if !args.build.quiet => transfer::missing_tools(),
+        _ => Vec::new(),
+    };
+
+    for tool in missing {
+        eprintln!("warning: {} not found: {}; {}", tool.name, tool.skipped, tool.hint);
+    }
+}
+
+fn main() {
+    dotenv().ok();
+
+    let cli = Cli::parse();
+
+    let root_directory = env::var("ROOT_DIRECTORY").expect("ROOT_DIRECTORY not set");
+    let fail_fast = cli.command.build_options().is_some_and(|build| build.fail_fast);
+    let source = source::from_root(&root_directory, cli.refresh, fail_fast);
+
+    report_missing_tools(&cli.command, &root_directory);
+
+    match &cli.command {
+        Command::Bundle(args) => run_bundle(source.as_ref(), args),
+        Command::Check(args) => run_check(source.as_ref(), args),
+        Command::Pack(args) => run_pack(source.as_ref(), args),
+        Command::Transfer(args) => run_transfer(source.as_ref(), args),
+        Command::Snapshot(args) => run_snapshot(source.as_ref(), args),
+        Command::WhoUses { symbol, module, max_import_depth } => run_who_uses(source.as_ref(), symbol, module, *max_import_depth),
+        Command::DeadHelpers { max_import_depth } => run_dead_helpers(source.as_ref(), *max_import_depth),
+        Command::CheckHelpers { max_import_depth } => run_check_helpers(source.as_ref(), *max_import_depth),
+        Command::List { group } => run_list(source.as_ref(), group.as_deref()),
+        Command::Extract { module, symbol } => run_extract(source.as_ref(), module, symbol),
+    }
 }
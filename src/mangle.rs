@@ -0,0 +1,615 @@
+use std::collections::{HashMap, HashSet};
+use regex::Regex;
+use rustpython_parser::ast::{self, Ranged};
+use rustpython_parser::Tok;
+use crate::python;
+
+const PYTHON_KEYWORDS: &[&str] = &[
+    "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class", "continue", "def", "del",
+    "elif", "else", "except", "finally", "for", "from", "global", "if", "import", "in", "is", "lambda", "nonlocal",
+    "not", "or", "pass", "raise", "return", "try", "while", "with", "yield",
+];
+
+const DYNAMIC_LOCALS: &[&str] = &["locals", "vars", "eval", "exec"];
+const DYNAMIC_GLOBALS: &[&str] = &["globals", "vars", "eval", "exec"];
+
+const MODULE: usize = 0;
+
+#[derive(PartialEq)]
+enum ScopeKind {
+    Module,
+    Function,
+    Class,
+    Comprehension,
+}
+
+struct Scope {
+    kind: ScopeKind,
+    parent: Option<usize>,
+    bound: Vec<String>,
+    globals: HashSet<String>,
+    nonlocals: HashSet<String>,
+    dynamic: bool,
+}
+
+struct Occurrence {
+    scope: usize,
+    name: String,
+    start: usize,
+}
+
+struct GlobalStatement {
+    start: usize,
+    end: usize,
+    names: Vec<String>,
+}
+
+struct Analysis<'a> {
+    source: &'a str,
+    scopes: Vec<Scope>,
+    occurrences: Vec<Occurrence>,
+    global_statements: Vec<GlobalStatement>,
+    pinned: HashSet<String>,
+    supported: bool,
+}
+
+impl Analysis<'_> {
+    fn new_scope(&mut self, kind: ScopeKind, parent: Option<usize>) -> usize {
+        self.scopes.push(Scope {
+            kind,
+            parent,
+            bound: Vec::new(),
+            globals: HashSet::new(),
+            nonlocals: HashSet::new(),
+            dynamic: false,
+        });
+        self.scopes.len() - 1
+    }
+
+    fn bind(&mut self, scope: usize, name: &str) {
+        let scope = if self.scopes[scope].globals.contains(name) { MODULE } else { scope };
+
+        if !self.scopes[scope].bound.iter().any(|bound| bound == name) {
+            self.scopes[scope].bound.push(name.to_string());
+        }
+    }
+
+    fn occur(&mut self, scope: usize, name: &str, start: usize) {
+        self.occurrences.push(Occurrence { scope, name: name.to_string(), start });
+    }
+
+    fn definition_name(&mut self, scope: usize, name: &str, start: usize) {
+        self.bind(scope, name);
+
+        let definition_re = Regex::new(&format!(r"\b(?:def|class)\s+({})\b", regex::escape(name))).unwrap();
+        match definition_re.captures(&self.source[start..]) {
+            Some(caps) => self.occur(scope, name, start + caps.get(1).unwrap().start()),
+            None => self.supported = false,
+        }
+    }
+
+    fn arguments(&mut self, arguments: &ast::Arguments, outer: usize, inner: usize) {
+        let with_defaults = arguments.posonlyargs.iter().chain(&arguments.args).chain(&arguments.kwonlyargs);
+
+        for argument in with_defaults {
+            if let Some(default) = &argument.default {
+                self.expr(default, outer);
+            }
+            self.arg(&argument.def, outer, inner);
+        }
+
+        for argument in arguments.vararg.iter().chain(&arguments.kwarg) {
+            self.arg(argument, outer, inner);
+        }
+    }
+
+    fn arg(&mut self, argument: &ast::Arg, outer: usize, inner: usize) {
+        if let Some(annotation) = &argument.annotation {
+            self.expr(annotation, outer);
+        }
+
+        self.bind(inner, &argument.arg);
+        self.occur(inner, &argument.arg, usize::from(argument.start()));
+    }
+
+    fn body(&mut self, body: &[ast::Stmt], scope: usize) {
+        for statement in body {
+            self.stmt(statement, scope);
+        }
+    }
+
+    fn function(&mut self, name: &str, start: usize, arguments: &ast::Arguments, body: &[ast::Stmt], scope: usize) {
+        self.definition_name(scope, name, start);
+
+        let inner = self.new_scope(ScopeKind::Function, Some(scope));
+        self.arguments(arguments, scope, inner);
+        self.body(body, inner);
+    }
+
+    fn stmt(&mut self, statement: &ast::Stmt, scope: usize) {
+        match statement {
+            ast::Stmt::FunctionDef(def) => {
+                def.decorator_list.iter().chain(def.returns.as_deref()).for_each(|expr| self.expr(expr, scope));
+                self.function(&def.name, usize::from(def.start()), &def.args, &def.body, scope);
+            }
+            ast::Stmt::AsyncFunctionDef(def) => {
+                def.decorator_list.iter().chain(def.returns.as_deref()).for_each(|expr| self.expr(expr, scope));
+                self.function(&def.name, usize::from(def.start()), &def.args, &def.body, scope);
+            }
+            ast::Stmt::ClassDef(def) => {
+                def.decorator_list.iter().chain(&def.bases).for_each(|expr| self.expr(expr, scope));
+                self.keywords(&def.keywords, scope);
+                self.definition_name(scope, &def.name, usize::from(def.start()));
+
+                let inner = self.new_scope(ScopeKind::Class, Some(scope));
+                self.body(&def.body, inner);
+            }
+            ast::Stmt::Return(statement) => statement.value.iter().for_each(|value| self.expr(value, scope)),
+            ast::Stmt::Delete(statement) => statement.targets.iter().for_each(|target| self.expr(target, scope)),
+            ast::Stmt::Assign(statement) => {
+                self.expr(&statement.value, scope);
+                statement.targets.iter().for_each(|target| self.expr(target, scope));
+            }
+            ast::Stmt::AugAssign(statement) => {
+                self.expr(&statement.value, scope);
+                self.expr(&statement.target, scope);
+            }
+            ast::Stmt::AnnAssign(statement) => {
+                self.expr(&statement.annotation, scope);
+                statement.value.iter().for_each(|value| self.expr(value, scope));
+                self.expr(&statement.target, scope);
+            }
+            ast::Stmt::For(statement) => {
+                self.expr(&statement.iter, scope);
+                self.expr(&statement.target, scope);
+                self.body(&statement.body, scope);
+                self.body(&statement.orelse, scope);
+            }
+            ast::Stmt::AsyncFor(statement) => {
+                self.expr(&statement.iter, scope);
+                self.expr(&statement.target, scope);
+                self.body(&statement.body, scope);
+                self.body(&statement.orelse, scope);
+            }
+            ast::Stmt::While(statement) => {
+                self.expr(&statement.test, scope);
+                self.body(&statement.body, scope);
+                self.body(&statement.orelse, scope);
+            }
+            ast::Stmt::If(statement) => {
+                self.expr(&statement.test, scope);
+                self.body(&statement.body, scope);
+                self.body(&statement.orelse, scope);
+            }
+            ast::Stmt::With(statement) => {
+                self.with_items(&statement.items, scope);
+                self.body(&statement.body, scope);
+            }
+            ast::Stmt::AsyncWith(statement) => {
+                self.with_items(&statement.items, scope);
+                self.body(&statement.body, scope);
+            }
+            ast::Stmt::Raise(statement) => {
+                statement.exc.iter().chain(&statement.cause).for_each(|expr| self.expr(expr, scope));
+            }
+            ast::Stmt::Try(statement) => {
+                self.body(&statement.body, scope);
+                for ast::ExceptHandler::ExceptHandler(handler) in &statement.handlers {
+                    handler.type_.iter().for_each(|expr| self.expr(expr, scope));
+                    if let Some(name) = &handler.name {
+                        self.pinned.insert(name.to_string());
+                        self.bind(scope, name);
+                    }
+                    self.body(&handler.body, scope);
+                }
+                self.body(&statement.orelse, scope);
+                self.body(&statement.finalbody, scope);
+            }
+            ast::Stmt::Assert(statement) => {
+                self.expr(&statement.test, scope);
+                statement.msg.iter().for_each(|msg| self.expr(msg, scope));
+            }
+            ast::Stmt::Import(ast::StmtImport { names, .. }) | ast::Stmt::ImportFrom(ast::StmtImportFrom { names, .. }) => {
+                for alias in names {
+                    let bound = alias.asname.as_deref().unwrap_or_else(|| alias.name.split('.').next().unwrap_or(&alias.name));
+                    self.pinned.insert(bound.to_string());
+                    self.bind(scope, bound);
+                }
+            }
+            ast::Stmt::Global(statement) => {
+                let names: Vec<String> = statement.names.iter().map(|name| name.to_string()).collect();
+                self.scopes[scope].globals.extend(names.iter().cloned());
+                self.global_statements.push(GlobalStatement {
+                    start: usize::from(statement.start()),
+                    end: usize::from(statement.end()),
+                    names,
+                });
+            }
+            ast::Stmt::Nonlocal(statement) => {
+                for name in &statement.names {
+                    self.pinned.insert(name.to_string());
+                    self.scopes[scope].nonlocals.insert(name.to_string());
+                }
+            }
+            ast::Stmt::Expr(statement) => self.expr(&statement.value, scope),
+            ast::Stmt::Pass(_) | ast::Stmt::Break(_) | ast::Stmt::Continue(_) => {}
+            _ => self.supported = false,
+        }
+    }
+
+    fn with_items(&mut self, items: &[ast::WithItem], scope: usize) {
+        for item in items {
+            self.expr(&item.context_expr, scope);
+            item.optional_vars.iter().for_each(|target| self.expr(target, scope));
+        }
+    }
+
+    fn keywords(&mut self, keywords: &[ast::Keyword], scope: usize) {
+        for keyword in keywords {
+            if let Some(name) = &keyword.arg {
+                self.pinned.insert(name.to_string());
+            }
+            self.expr(&keyword.value, scope);
+        }
+    }
+
+    fn comprehension(&mut self, generators: &[ast::Comprehension], elements: &[&ast::Expr], scope: usize) {
+        let inner = self.new_scope(ScopeKind::Comprehension, Some(scope));
+
+        for (index, generator) in generators.iter().enumerate() {
+            self.expr(&generator.iter, if index == 0 { scope } else { inner });
+            self.expr(&generator.target, inner);
+            generator.ifs.iter().for_each(|condition| self.expr(condition, inner));
+        }
+
+        elements.iter().for_each(|element| self.expr(element, inner));
+    }
+
+    fn expr(&mut self, expr: &ast::Expr, scope: usize) {
+        match expr {
+            ast::Expr::BoolOp(expr) => expr.values.iter().for_each(|value| self.expr(value, scope)),
+            ast::Expr::NamedExpr(expr) => {
+                self.expr(&expr.value, scope);
+
+                let mut target_scope = scope;
+                while self.scopes[target_scope].kind == ScopeKind::Comprehension {
+                    target_scope = self.scopes[target_scope].parent.unwrap_or(MODULE);
+                }
+
+                if let ast::Expr::Name(name) = &*expr.target {
+                    self.bind(target_scope, &name.id);
+                }
+                self.expr(&expr.target, scope);
+            }
+            ast::Expr::BinOp(expr) => {
+                self.expr(&expr.left, scope);
+                self.expr(&expr.right, scope);
+            }
+            ast::Expr::UnaryOp(expr) => self.expr(&expr.operand, scope),
+            ast::Expr::Lambda(expr) => {
+                let inner = self.new_scope(ScopeKind::Function, Some(scope));
+                self.arguments(&expr.args, scope, inner);
+                self.expr(&expr.body, inner);
+            }
+            ast::Expr::IfExp(expr) => {
+                self.expr(&expr.test, scope);
+                self.expr(&expr.body, scope);
+                self.expr(&expr.orelse, scope);
+            }
+            ast::Expr::Dict(expr) => {
+                expr.keys.iter().flatten().chain(&expr.values).for_each(|value| self.expr(value, scope));
+            }
+            ast::Expr::Set(expr) => expr.elts.iter().for_each(|elt| self.expr(elt, scope)),
+            ast::Expr::ListComp(expr) => self.comprehension(&expr.generators, &[&expr.elt], scope),
+            ast::Expr::SetComp(expr) => self.comprehension(&expr.generators, &[&expr.elt], scope),
+            ast::Expr::GeneratorExp(expr) => self.comprehension(&expr.generators, &[&expr.elt], scope),
+            ast::Expr::DictComp(expr) => self.comprehension(&expr.generators, &[&expr.key, &expr.value], scope),
+            ast::Expr::Await(expr) => self.expr(&expr.value, scope),
+            ast::Expr::Yield(expr) => expr.value.iter().for_each(|value| self.expr(value, scope)),
+            ast::Expr::YieldFrom(expr) => self.expr(&expr.value, scope),
+            ast::Expr::Compare(expr) => {
+                self.expr(&expr.left, scope);
+                expr.comparators.iter().for_each(|comparator| self.expr(comparator, scope));
+            }
+            ast::Expr::Call(expr) => {
+                self.expr(&expr.func, scope);
+                expr.args.iter().for_each(|arg| self.expr(arg, scope));
+                self.keywords(&expr.keywords, scope);
+            }
+            ast::Expr::FormattedValue(expr) => {
+                self.expr(&expr.value, scope);
+                expr.format_spec.iter().for_each(|spec| self.expr(spec, scope));
+            }
+            ast::Expr::JoinedStr(expr) => expr.values.iter().for_each(|value| self.expr(value, scope)),
+            ast::Expr::Constant(_) => {}
+            ast::Expr::Attribute(expr) => self.expr(&expr.value, scope),
+            ast::Expr::Subscript(expr) => {
+                self.expr(&expr.value, scope);
+                self.expr(&expr.slice, scope);
+            }
+            ast::Expr::Starred(expr) => self.expr(&expr.value, scope),
+            ast::Expr::Name(name) => {
+                if DYNAMIC_LOCALS.contains(&name.id.as_str()) {
+                    let mut current = Some(scope);
+                    while let Some(index) = current {
+                        self.scopes[index].dynamic = true;
+                        current = self.scopes[index].parent;
+                    }
+                }
+                if DYNAMIC_GLOBALS.contains(&name.id.as_str()) {
+                    self.scopes[MODULE].dynamic = true;
+                }
+
+                if !matches!(name.ctx, ast::ExprContext::Load) {
+                    self.bind(scope, &name.id);
+                }
+                self.occur(scope, &name.id, usize::from(name.start()));
+            }
+            ast::Expr::List(expr) => expr.elts.iter().for_each(|elt| self.expr(elt, scope)),
+            ast::Expr::Tuple(expr) => expr.elts.iter().for_each(|elt| self.expr(elt, scope)),
+            ast::Expr::Slice(expr) => {
+                expr.lower.iter().chain(&expr.upper).chain(&expr.step).for_each(|value| self.expr(value, scope));
+            }
+        }
+    }
+
+    fn resolve(&self, scope: usize, name: &str) -> Option<usize> {
+        let mut current = scope;
+
+        loop {
+            let candidate = &self.scopes[current];
+            let visible = current == scope || candidate.kind != ScopeKind::Class;
+
+            if visible && candidate.globals.contains(name) {
+                return Some(MODULE);
+            }
+            if visible && candidate.nonlocals.contains(name) {
+                return None;
+            }
+            if visible && candidate.bound.iter().any(|bound| bound == name) {
+                return Some(current);
+            }
+
+            current = candidate.parent?;
+        }
+    }
+}
+
+fn short_name(mut index: usize) -> String {
+    let mut name = Vec::new();
+
+    loop {
+        name.push(b'a' + (index % 26) as u8);
+        if index < 26 {
+            break;
+        }
+        index = index / 26 - 1;
+    }
+
+    name.reverse();
+    String::from_utf8(name).unwrap()
+}
+
+fn is_private(name: &str) -> bool {
+    name.starts_with('_') && !name.starts_with("__") && name.len() > 1
+}
+
+fn fresh_name(prefix: &str, next: &mut usize, excluded: &HashSet<String>) -> String {
+    loop {
+        let name = format!("{}{}", prefix, short_name(*next));
+        *next += 1;
+
+        if !excluded.contains(&name) && !PYTHON_KEYWORDS.contains(&name.as_str()) {
+            return name;
+        }
+    }
+}
+
+pub fn mangle(lines: Vec<String>) -> Vec<String> {
+    let parsed = python::parse_source(&lines);
+    let source = &parsed.source;
+
+    let Ok(suite) = &parsed.suite else {
+        return lines;
+    };
+
+    let mut analysis = Analysis {
+        source,
+        scopes: Vec::new(),
+        occurrences: Vec::new(),
+        global_statements: Vec::new(),
+        pinned: HashSet::new(),
+        supported: true,
+    };
+    analysis.new_scope(ScopeKind::Module, None);
+    analysis.body(suite, MODULE);
+
+    if !analysis.supported {
+        return lines;
+    }
+
+    let taken: HashSet<String> = parsed
+        .tokens
+        .iter()
+        .filter_map(|(token, _, _)| match token {
+            Tok::Name { name } => Some(name.clone()),
+            _ => None,
+        })
+        .collect();
+
+    let mut renames: HashMap<(usize, String), String> = HashMap::new();
+    let mut scope_renames: Vec<HashSet<String>> = Vec::new();
+
+    for (index, scope) in analysis.scopes.iter().enumerate() {
+        let mut excluded = taken.clone();
+        let mut current = scope.parent;
+        while let Some(parent) = current {
+            excluded.extend(scope_renames[parent].iter().cloned());
+            current = analysis.scopes[parent].parent;
+        }
+
+        let mut assigned = HashSet::new();
+        let mut next = 0;
+
+        for name in &scope.bound {
+            let renamable = match scope.kind {
+                ScopeKind::Module => is_private(name),
+                ScopeKind::Function | ScopeKind::Comprehension => !name.starts_with("__"),
+                ScopeKind::Class => false,
+            };
+
+            if !renamable || scope.dynamic || analysis.pinned.contains(name) {
+                continue;
+            }
+
+            let prefix = if scope.kind == ScopeKind::Module { "_" } else { "" };
+            let new_name = fresh_name(prefix, &mut next, &excluded);
+            excluded.insert(new_name.clone());
+            assigned.insert(new_name.clone());
+            renames.insert((index, name.clone()), new_name);
+        }
+
+        scope_renames.push(assigned);
+    }
+
+    let mut edits: Vec<(usize, usize, String)> = Vec::new();
+
+    for occurrence in &analysis.occurrences {
+        let Some(binding) = analysis.resolve(occurrence.scope, &occurrence.name) else {
+            continue;
+        };
+        let Some(new_name) = renames.get(&(binding, occurrence.name.clone())) else {
+            continue;
+        };
+
+        let end = occurrence.start + occurrence.name.len();
+        if source.get(occurrence.start..end) != Some(occurrence.name.as_str()) {
+            return lines;
+        }
+
+        edits.push((occurrence.start, end, new_name.clone()));
+    }
+
+    for statement in &analysis.global_statements {
+        let names: Vec<String> = statement
+            .names
+            .iter()
+            .map(|name| renames.get(&(MODULE, name.clone())).unwrap_or(name).clone())
+            .collect();
+
+        if names != statement.names {
+            edits.push((statement.start, statement.end, format!("global {}", names.join(", "))));
+        }
+    }
+
+    edits.sort_by_key(|edit| std::cmp::Reverse(edit.0));
+
+    let mut mangled = source.to_string();
+    for (start, end, new_name) in edits {
+        mangled.replace_range(start..end, &new_name);
+    }
+
+    mangled.split('\n').map(|line| line.to_string()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use rustpython_parser::Parse;
+    use super::*;
+
+    fn resolution(source: &str) -> Vec<(Option<usize>, usize)> {
+        let suite = ast::Suite::parse(source, "<test>").expect("mangled output should parse");
+
+        let mut analysis = Analysis {
+            source,
+            scopes: Vec::new(),
+            occurrences: Vec::new(),
+            global_statements: Vec::new(),
+            pinned: HashSet::new(),
+            supported: true,
+        };
+        analysis.new_scope(ScopeKind::Module, None);
+        analysis.body(&suite, MODULE);
+
+        let mut bindings: Vec<(Option<usize>, String)> = Vec::new();
+
+        analysis
+            .occurrences
+            .iter()
+            .map(|occurrence| {
+                let binding = (analysis.resolve(occurrence.scope, &occurrence.name), occurrence.name.clone());
+                let id = bindings.iter().position(|seen| *seen == binding).unwrap_or_else(|| {
+                    bindings.push(binding.clone());
+                    bindings.len() - 1
+                });
+
+                (binding.0, id)
+            })
+            .collect()
+    }
+
+    fn mangled(source: &str) -> String {
+        let output = mangle(source.lines().map(|line| line.to_string()).collect()).join("\n");
+
+        assert_eq!(resolution(&output), resolution(source), "identifiers resolve differently in:\n{}", output);
+
+        output
+    }
+
+    #[test]
+    fn nested_closures_keep_reading_their_enclosing_locals() {
+        assert_eq!(
+            mangled("def outer(count):\n    total = 0\n    def inner(step):\n        return total + step * count\n    return inner(1)\nprint(outer(2))"),
+            "def outer(a):\n    b = 0\n    def c(d):\n        return b + d * a\n    return c(1)\nprint(outer(2))"
+        );
+    }
+
+    #[test]
+    fn global_and_nonlocal_names_stay_linked() {
+        assert_eq!(
+            mangled("_score = 0\ndef bump(points):\n    global _score\n    _score += points\ndef counter():\n    hits = 0\n    def hit():\n        nonlocal hits\n        hits += 1\n        return hits\n    return hit"),
+            "_a = 0\ndef bump(a):\n    global _a\n    _a += a\ndef counter():\n    hits = 0\n    def a():\n        nonlocal hits\n        hits += 1\n        return hits\n    return a"
+        );
+    }
+
+    #[test]
+    fn methods_do_not_see_class_body_names() {
+        assert_eq!(
+            mangled("def build():\n    size = 3\n    class Board:\n        size = 8\n        def cells(self):\n            return size * size\n    return Board"),
+            "def build():\n    a = 3\n    class b:\n        size = 8\n        def cells(c):\n            return a * a\n    return b"
+        );
+    }
+
+    #[test]
+    fn comprehension_targets_get_their_own_scope() {
+        assert_eq!(
+            mangled("def squares(n):\n    x = 10\n    values = [x * x for x in range(n)]\n    return values, x"),
+            "def squares(a):\n    b = 10\n    c = [d * d for d in range(a)]\n    return c, b"
+        );
+    }
+
+    #[test]
+    fn keyword_argument_names_are_pinned() {
+        let source = "def area(width, height):\n    return width * height\nprint(area(width=2, height=3))";
+
+        assert_eq!(mangled(source), source);
+    }
+
+    #[test]
+    fn dynamic_lookups_leave_their_scopes_alone() {
+        let source = "_label = 'v'\ndef show(value):\n    label = 'v'\n    return eval('label') + str(locals())\ndef total(items):\n    return sum(items)";
+
+        assert_eq!(
+            mangled(source),
+            "_label = 'v'\ndef show(value):\n    label = 'v'\n    return eval('label') + str(locals())\ndef total(a):\n    return sum(a)"
+        );
+    }
+
+    #[test]
+    fn private_module_names_are_renamed_consistently_across_helpers() {
+        assert_eq!(
+            mangled("_cache = {}\ndef _lookup(key):\n    return _cache.get(key)\ndef fetch(key):\n    return _lookup(key)\nprint(fetch(1), _lookup(2), _cache)"),
+            "_a = {}\ndef _b(a):\n    return _a.get(a)\ndef fetch(a):\n    return _b(a)\nprint(fetch(1), _b(2), _a)"
+        );
+    }
+}
@@ -0,0 +1,186 @@
+use rustpython_parser::ast::{self, Ranged};
+use rustpython_parser::Tok;
+use crate::license::ATTRIBUTION_HEADER;
+use crate::python::{self, line_index};
+
+struct Docstring {
+    start: usize,
+    end: usize,
+    only_statement: bool,
+}
+
+fn is_string_statement(statement: &ast::Stmt) -> bool {
+    match statement {
+        ast::Stmt::Expr(expr) => matches!(&*expr.value, ast::Expr::Constant(constant) if matches!(constant.value, ast::Constant::Str(_))),
+        _ => false,
+    }
+}
+
+fn collect_docstrings(body: &[ast::Stmt], has_docstring: bool, docstrings: &mut Vec<Docstring>) {
+    if let Some(first) = body.first().filter(|first| has_docstring && is_string_statement(first)) {
+        docstrings.push(Docstring {
+            start: usize::from(first.start()),
+            end: usize::from(first.end()),
+            only_statement: body.len() == 1,
+        });
+    }
+
+    for statement in body {
+        match statement {
+            ast::Stmt::FunctionDef(def) => collect_docstrings(&def.body, true, docstrings),
+            ast::Stmt::AsyncFunctionDef(def) => collect_docstrings(&def.body, true, docstrings),
+            ast::Stmt::ClassDef(def) => collect_docstrings(&def.body, true, docstrings),
+            ast::Stmt::If(block) => {
+                collect_docstrings(&block.body, false, docstrings);
+                collect_docstrings(&block.orelse, false, docstrings);
+            }
+            ast::Stmt::For(block) => {
+                collect_docstrings(&block.body, false, docstrings);
+                collect_docstrings(&block.orelse, false, docstrings);
+            }
+            ast::Stmt::While(block) => {
+                collect_docstrings(&block.body, false, docstrings);
+                collect_docstrings(&block.orelse, false, docstrings);
+            }
+            ast::Stmt::With(block) => collect_docstrings(&block.body, false, docstrings),
+            ast::Stmt::Try(block) => {
+                collect_docstrings(&block.body, false, docstrings);
+                for ast::ExceptHandler::ExceptHandler(handler) in &block.handlers {
+                    collect_docstrings(&handler.body, false, docstrings);
+                }
+                collect_docstrings(&block.orelse, false, docstrings);
+                collect_docstrings(&block.finalbody, false, docstrings);
+            }
+            _ => {}
+        }
+    }
+}
+
+pub fn minify(lines: Vec<String>) -> Vec<String> {
+    let parsed = python::parse_source(&lines);
+    let line_starts = &parsed.line_starts;
+
+    let Ok(suite) = &parsed.suite else {
+        return lines;
+    };
+
+    let mut docstrings = Vec::new();
+    collect_docstrings(suite, true, &mut docstrings);
+
+    let mut removed = vec![false; lines.len()];
+    let mut replacements: Vec<Option<String>> = vec![None; lines.len()];
+
+    for docstring in docstrings {
+        let first_line = line_index(line_starts, docstring.start);
+        let last_line = line_index(line_starts, docstring.end.saturating_sub(1));
+        let before = &lines[first_line][..docstring.start - line_starts[first_line]];
+        let after = lines[last_line][docstring.end - line_starts[last_line]..].trim_start();
+
+        if !before.trim().is_empty() || !(after.is_empty() || after.starts_with('#')) {
+            continue;
+        }
+
+        removed[first_line..=last_line].iter_mut().for_each(|line| *line = true);
+
+        if docstring.only_statement {
+            replacements[first_line] = Some(format!("{}pass", before));
+        }
+    }
+
+    let mut comment_columns: Vec<Option<usize>> = vec![None; lines.len()];
+    let mut starts_in_string = vec![false; lines.len()];
+    let mut ends_in_string = vec![false; lines.len()];
+
+    for (token, start, end) in &parsed.tokens {
+        let start = *start;
+        let first_line = line_index(line_starts, start);
+
+        match token {
+            Tok::Comment(_) => comment_columns[first_line] = Some(start - line_starts[first_line]),
+            Tok::String { .. } => {
+                let last_line = line_index(line_starts, end.saturating_sub(1));
+                starts_in_string[first_line + 1..=last_line].iter_mut().for_each(|line| *line = true);
+                ends_in_string[first_line..last_line].iter_mut().for_each(|line| *line = true);
+            }
+            _ => {}
+        }
+    }
+
+    let mut output_lines = Vec::new();
+    let mut in_attribution = false;
+
+    for (index, line) in lines.into_iter().enumerate() {
+        in_attribution = line == ATTRIBUTION_HEADER || (in_attribution && line.starts_with('#'));
+
+        if in_attribution {
+            output_lines.push(line);
+            continue;
+        }
+
+        if removed[index] {
+            output_lines.extend(replacements[index].take());
+            continue;
+        }
+
+        let text = match comment_columns[index] {
+            Some(column) => line[..column].trim_end(),
+            None if ends_in_string[index] => &line,
+            None => line.trim_end(),
+        };
+
+        if text.trim().is_empty() && !starts_in_string[index] {
+            continue;
+        }
+
+        output_lines.push(text.to_string());
+    }
+
+    output_lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn minified(source: &str) -> String {
+        minify(source.lines().map(|line| line.to_string()).collect()).join("\n")
+    }
+
+    #[test]
+    fn docstring_only_bodies_become_pass() {
+        assert_eq!(
+            minified("def stub():\n    \"\"\"Filled in later.\"\"\"\n\nclass Empty:\n    '''Nothing yet.\n\n    Really.'''\nstub()"),
+            "def stub():\n    pass\nclass Empty:\n    pass\nstub()"
+        );
+    }
+
+    #[test]
+    fn docstrings_before_code_are_dropped() {
+        assert_eq!(
+            minified("\"\"\"Module notes.\"\"\"\ndef area(w, h):\n    \"\"\"Width times height.\"\"\"\n    return w * h"),
+            "def area(w, h):\n    return w * h"
+        );
+    }
+
+    #[test]
+    fn comments_after_string_literals_are_stripped_but_hashes_inside_strings_stay() {
+        assert_eq!(
+            minified("label = \"# of lives\"  # shown on the HUD\nprint('#', label) # debug"),
+            "label = \"# of lives\"\nprint('#', label)"
+        );
+    }
+
+    #[test]
+    fn multi_line_strings_keep_their_blank_lines() {
+        let source = "BANNER = \"\"\"\n  SNAKE   \n\n  press any key\n\"\"\"\n\n\nprint(BANNER)";
+
+        assert_eq!(minified(source), "BANNER = \"\"\"\n  SNAKE   \n\n  press any key\n\"\"\"\nprint(BANNER)");
+    }
+
+    #[test]
+    fn keeps_the_attribution_header() {
+        let source = format!("{}\n# helpers.py: MIT\n\n# a comment\nx = 1", ATTRIBUTION_HEADER);
+
+        assert_eq!(minified(&source), format!("{}\n# helpers.py: MIT\nx = 1", ATTRIBUTION_HEADER));
+    }
+}
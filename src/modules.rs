@@ -0,0 +1,98 @@
+use rustpython_parser::ast::{self, Ranged};
+use crate::python;
+
+const CALCULATOR_MODULES: &[&str] = &[
+    "array", "builtins", "collections", "gc", "math", "random", "sys", "time", "ti_draw", "ti_graphics", "ti_hub",
+    "ti_image", "ti_plotlib", "ti_rover", "ti_system", "turtle", "ce_box", "ce_chart", "ce_quivr", "ce_turtl",
+];
+
+const IMPORT_GUARDS: &[&str] = &["ImportError", "ModuleNotFoundError", "Exception"];
+
+pub struct UnsupportedImport {
+    pub line_number: usize,
+    pub line: String,
+    pub module: String,
+}
+
+fn is_calculator_module(module: &str) -> bool {
+    CALCULATOR_MODULES.contains(&module.split('.').next().unwrap_or(module))
+}
+
+fn guards_imports(handlers: &[ast::ExceptHandler]) -> bool {
+    handlers.iter().any(|ast::ExceptHandler::ExceptHandler(handler)| match handler.type_.as_deref() {
+        None => true,
+        Some(ast::Expr::Name(name)) => IMPORT_GUARDS.contains(&name.id.as_str()),
+        Some(ast::Expr::Tuple(tuple)) => tuple
+            .elts
+            .iter()
+            .any(|elt| matches!(elt, ast::Expr::Name(name) if IMPORT_GUARDS.contains(&name.id.as_str()))),
+        Some(_) => false,
+    })
+}
+
+fn collect_imports(body: &[ast::Stmt], imports: &mut Vec<(usize, String)>) {
+    for statement in body {
+        match statement {
+            ast::Stmt::Import(import) => {
+                imports.extend(import.names.iter().map(|alias| (usize::from(statement.start()), alias.name.to_string())));
+            }
+            ast::Stmt::ImportFrom(import) if import.level.as_ref().map(|level| level.to_usize()).unwrap_or(0) == 0 => {
+                if let Some(module) = &import.module {
+                    imports.push((usize::from(statement.start()), module.to_string()));
+                }
+            }
+            ast::Stmt::FunctionDef(def) => collect_imports(&def.body, imports),
+            ast::Stmt::AsyncFunctionDef(def) => collect_imports(&def.body, imports),
+            ast::Stmt::ClassDef(def) => collect_imports(&def.body, imports),
+            ast::Stmt::For(block) => {
+                collect_imports(&block.body, imports);
+                collect_imports(&block.orelse, imports);
+            }
+            ast::Stmt::While(block) => {
+                collect_imports(&block.body, imports);
+                collect_imports(&block.orelse, imports);
+            }
+            ast::Stmt::If(block) => {
+                collect_imports(&block.body, imports);
+                collect_imports(&block.orelse, imports);
+            }
+            ast::Stmt::With(block) => collect_imports(&block.body, imports),
+            ast::Stmt::Try(block) => {
+                if !guards_imports(&block.handlers) {
+                    collect_imports(&block.body, imports);
+                }
+                for ast::ExceptHandler::ExceptHandler(handler) in &block.handlers {
+                    collect_imports(&handler.body, imports);
+                }
+                collect_imports(&block.orelse, imports);
+                collect_imports(&block.finalbody, imports);
+            }
+            _ => {}
+        }
+    }
+}
+
+pub fn find_unsupported_imports(lines: &[String]) -> Vec<UnsupportedImport> {
+    let parsed = python::parse_source(lines);
+
+    let Ok(suite) = &parsed.suite else {
+        return Vec::new();
+    };
+
+    let mut imports = Vec::new();
+    collect_imports(suite, &mut imports);
+
+    imports
+        .into_iter()
+        .filter(|(_, module)| !is_calculator_module(module))
+        .map(|(offset, module)| {
+            let index = parsed.line_index(offset);
+
+            UnsupportedImport {
+                line_number: index + 1,
+                line: lines.get(index).map(|line| line.trim().to_string()).unwrap_or_default(),
+                module,
+            }
+        })
+        .collect()
+}
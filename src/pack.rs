@@ -0,0 +1,159 @@
+use std::collections::HashSet;
+use crate::graph::json_string;
+use crate::FileObject;
+
+pub const LAUNCHER_NAME: &str = "menu";
+
+const APPVAR_NAME_LENGTH: usize = 8;
+
+pub fn appvar_names(script_names: &[String]) -> Vec<String> {
+    let mut taken = HashSet::from([LAUNCHER_NAME.to_ascii_uppercase()]);
+
+    script_names
+        .iter()
+        .map(|script_name| {
+            let mut base: String = script_name.chars().filter(|c| c.is_ascii_alphanumeric()).collect::<String>().to_ascii_uppercase();
+            if !base.starts_with(|c: char| c.is_ascii_alphabetic()) {
+                base.insert(0, 'P');
+            }
+
+            let mut name = base[..base.len().min(APPVAR_NAME_LENGTH)].to_string();
+            let mut suffix = 2;
+
+            while !taken.insert(name.clone()) {
+                let digits = suffix.to_string();
+                name = format!("{}{}", &base[..base.len().min(APPVAR_NAME_LENGTH - digits.len())], digits);
+                suffix += 1;
+            }
+
+            name
+        })
+        .collect()
+}
+
+fn python_string(value: &str) -> String {
+    let mut quoted = String::from("\"");
+
+    for c in value.chars() {
+        match c {
+            '\\' => quoted.push_str("\\\\"),
+            '"' => quoted.push_str("\\\""),
+            ' '..='~' => quoted.push(c),
+            c if (c as u32) <= 0xffff => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push_str(&format!("\\U{:08x}", c as u32)),
+        }
+    }
+
+    quoted.push('"');
+    quoted
+}
+
+pub fn launcher(script_names: &[String]) -> FileObject {
+    let quoted: Vec<String> = script_names.iter().map(|name| python_string(name)).collect();
+
+    let contents = vec![
+        String::from("# Generated by ti-84-python-compiler pack"),
+        format!("SCRIPTS = [{}]", quoted.join(", ")),
+        String::from("for i, name in enumerate(SCRIPTS):"),
+        String::from("    print(str(i + 1) + \": \" + name)"),
+        String::from("choice = int(input(\"Run #: \"))"),
+        String::from("if 1 <= choice <= len(SCRIPTS):"),
+        String::from("    __import__(SCRIPTS[choice - 1])"),
+    ];
+
+    FileObject {
+        script_name: LAUNCHER_NAME.to_string(),
+        contents,
+    }
+}
+
+pub fn readme(group: &str, files: &[FileObject], appvar_names: &[String], revision: Option<&str>) -> String {
+    let mut lines = vec![
+        format!("# {} for the TI-84 Plus CE Python", group),
+        String::new(),
+        format!(
+            "Built by ti-84-python-compiler {} from source revision {}.",
+            env!("CARGO_PKG_VERSION"),
+            revision.unwrap_or("unknown")
+        ),
+        String::new(),
+        String::from("## Programs"),
+        String::new(),
+        String::from("| Program | On the calculator | Lines | Bytes |"),
+        String::from("| --- | --- | ---: | ---: |"),
+    ];
+
+    for (file, appvar_name) in files.iter().zip(appvar_names) {
+        lines.push(format!(
+            "| {} | {} | {} | {} |",
+            file.script_name,
+            appvar_name,
+            file.contents.len(),
+            file.contents.join("\n").len()
+        ));
+    }
+
+    lines.extend([
+        String::new(),
+        String::from("## Installing"),
+        String::new(),
+        String::from("1. Connect the calculator and open TI Connect CE."),
+        String::from("2. Drag every file in the `8xv` folder onto the calculator and send it to Archive."),
+        format!("3. Open the Python App and run `{}` to pick a program, or run any program directly.", LAUNCHER_NAME.to_uppercase()),
+        String::new(),
+        format!("`{}.zip` holds the same programs as plain `.py` files for reading or editing on a computer.", group),
+    ]);
+
+    lines.join("\n")
+}
+
+pub fn metadata_json(group: &str, files: &[FileObject], appvar_names: &[String], revision: Option<&str>) -> String {
+    let programs: Vec<String> = files
+        .iter()
+        .zip(appvar_names)
+        .map(|(file, appvar_name)| {
+            format!(
+                "{{\"name\":{},\"appvar\":{},\"lines\":{},\"bytes\":{}}}",
+                json_string(&file.script_name),
+                json_string(appvar_name),
+                file.contents.len(),
+                file.contents.join("\n").len()
+            )
+        })
+        .collect();
+
+    format!(
+        "{{\"group\":{},\"compiler\":\"{}\",\"revision\":{},\"launcher\":\"{}\",\"programs\":[{}]}}",
+        json_string(group),
+        env!("CARGO_PKG_VERSION"),
+        revision.map(json_string).unwrap_or_else(|| String::from("null")),
+        LAUNCHER_NAME,
+        programs.join(",")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(script_names: &[&str]) -> Vec<String> {
+        appvar_names(&script_names.iter().map(|name| name.to_string()).collect::<Vec<String>>())
+    }
+
+    #[test]
+    fn appvar_names_fit_the_calculator() {
+        assert_eq!(names(&["snake", "snake_game", "2048", "__"]), vec!["SNAKE", "SNAKEGAM", "P2048", "P"]);
+    }
+
+    #[test]
+    fn appvar_names_never_collide() {
+        assert_eq!(names(&["space_invaders", "space-invaders2", "spaceinvaders", "Menu"]), vec!["SPACEINV", "SPACEIN2", "SPACEIN3", "MENU2"]);
+    }
+
+    #[test]
+    fn launcher_quotes_names_as_python_strings() {
+        let launcher = launcher(&[String::from("SNAKE"), String::from("say \"hi\"\\")]);
+
+        assert_eq!(launcher.contents[1], "SCRIPTS = [\"SNAKE\", \"say \\\"hi\\\"\\\\\"]");
+    }
+}
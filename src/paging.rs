@@ -0,0 +1,127 @@
+use rustpython_parser::ast::{self, Ranged};
+use crate::python;
+use crate::subset::Profile;
+
+const HELPER_NAME: &str = "paged_print";
+
+pub struct Pagination {
+    pub lines: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+fn helper(profile: &Profile) -> Vec<String> {
+    vec![
+        String::from("_paged_rows = [0]"),
+        format!("def {}(*args):", HELPER_NAME),
+        String::from("    for line in \" \".join([str(arg) for arg in args]).split(\"\\n\"):"),
+        format!("        rows = max(1, (len(line) + {}) // {})", profile.columns - 1, profile.columns),
+        format!("        if _paged_rows[0] + rows > {}:", profile.rows - 1),
+        String::from("            input(\"-- more --\")"),
+        String::from("            _paged_rows[0] = 0"),
+        String::from("        print(line)"),
+        String::from("        _paged_rows[0] += rows"),
+    ]
+}
+
+fn printed_lines(statement: &ast::Stmt) -> Option<usize> {
+    let ast::Stmt::Expr(expr) = statement else {
+        return None;
+    };
+    let ast::Expr::Call(call) = &*expr.value else {
+        return None;
+    };
+
+    if !matches!(&*call.func, ast::Expr::Name(name) if name.id.as_str() == "print") || !call.keywords.is_empty() {
+        return None;
+    }
+
+    let newlines: usize = call
+        .args
+        .iter()
+        .map(|arg| match arg {
+            ast::Expr::Constant(ast::ExprConstant { value: ast::Constant::Str(text), .. }) => text.matches('\n').count(),
+            _ => 0,
+        })
+        .sum();
+
+    Some(newlines + 1)
+}
+
+fn collect_runs(body: &[ast::Stmt], profile: &Profile, runs: &mut Vec<(Vec<usize>, usize)>) {
+    let mut run: (Vec<usize>, usize) = (Vec::new(), 0);
+
+    for statement in body.iter().map(Some).chain([None]) {
+        if let Some((print, lines)) = statement.and_then(|statement| printed_lines(statement).map(|lines| (statement, lines))) {
+            run.0.push(usize::from(print.start()));
+            run.1 += lines;
+            continue;
+        }
+
+        if run.1 > profile.rows {
+            runs.push(std::mem::take(&mut run));
+        }
+        run = (Vec::new(), 0);
+
+        match statement {
+            Some(ast::Stmt::FunctionDef(def)) => collect_runs(&def.body, profile, runs),
+            Some(ast::Stmt::For(block)) => collect_runs(&block.body, profile, runs),
+            Some(ast::Stmt::While(block)) => collect_runs(&block.body, profile, runs),
+            Some(ast::Stmt::If(block)) => {
+                collect_runs(&block.body, profile, runs);
+                collect_runs(&block.orelse, profile, runs);
+            }
+            Some(ast::Stmt::With(block)) => collect_runs(&block.body, profile, runs),
+            Some(ast::Stmt::Try(block)) => {
+                collect_runs(&block.body, profile, runs);
+                collect_runs(&block.orelse, profile, runs);
+                collect_runs(&block.finalbody, profile, runs);
+            }
+            _ => {}
+        }
+    }
+}
+
+pub fn paginate(lines: Vec<String>, profile: &Profile) -> Pagination {
+    let parsed = python::parse_source(&lines);
+
+    let Ok(suite) = &parsed.suite else {
+        return Pagination { lines, warnings: Vec::new() };
+    };
+
+    if suite.iter().any(|statement| matches!(statement, ast::Stmt::FunctionDef(def) if def.name.as_str() == HELPER_NAME)) {
+        return Pagination { lines, warnings: vec![format!("1: {} is already defined, so long print sequences were left unpaged", HELPER_NAME)] };
+    }
+
+    let mut runs = Vec::new();
+    collect_runs(suite, profile, &mut runs);
+
+    if runs.is_empty() {
+        return Pagination { lines, warnings: Vec::new() };
+    }
+
+    let helper = helper(profile);
+
+    let warnings = runs
+        .iter()
+        .map(|(starts, printed)| {
+            format!(
+                "{}: {} consecutive print() calls write about {} lines, more than the {}-row screen; they now pause with {}()",
+                parsed.line_index(starts[0]) + 1 + helper.len(),
+                starts.len(),
+                printed,
+                profile.rows,
+                HELPER_NAME
+            )
+        })
+        .collect();
+
+    let mut paged = parsed.source.clone();
+    for start in runs.into_iter().flat_map(|(starts, _)| starts).rev() {
+        paged.replace_range(start..start + "print".len(), HELPER_NAME);
+    }
+
+    let mut paged_lines = helper;
+    paged_lines.extend(paged.split('\n').map(|line| line.to_string()));
+
+    Pagination { lines: paged_lines, warnings }
+}
@@ -0,0 +1,170 @@
+use std::collections::HashMap;
+use rustpython_parser::ast::{self, Ranged};
+use rustpython_parser::Tok;
+use crate::python::{self, line_index};
+use crate::rules::Violation;
+
+const REPEATED_LOOKUPS: usize = 3;
+
+struct Linter<'a> {
+    lines: &'a [String],
+    line_starts: &'a [usize],
+    tokens: &'a [(Tok, usize, usize)],
+    findings: Vec<Violation>,
+}
+
+fn is_string_expr(expr: &ast::Expr) -> bool {
+    match expr {
+        ast::Expr::Constant(constant) => matches!(constant.value, ast::Constant::Str(_)),
+        ast::Expr::JoinedStr(_) => true,
+        ast::Expr::Call(call) => matches!(&*call.func, ast::Expr::Name(name) if name.id.as_str() == "str"),
+        ast::Expr::BinOp(binop) => is_string_expr(&binop.left) || is_string_expr(&binop.right),
+        _ => false,
+    }
+}
+
+impl Linter<'_> {
+    fn flag(&mut self, rule_id: &'static str, offset: usize, message: &'static str) {
+        let line_number = line_index(self.line_starts, offset) + 1;
+
+        if self.findings.iter().any(|finding| finding.rule_id == rule_id && finding.line_number == line_number) {
+            return;
+        }
+
+        self.findings.push(Violation {
+            rule_id,
+            line_number,
+            line: self.lines.get(line_number - 1).map(|line| line.trim().to_string()).unwrap_or_default(),
+            message,
+        });
+    }
+
+    fn loop_tokens(&self, body: &[ast::Stmt]) -> Vec<(Tok, usize)> {
+        let (Some(first), Some(last)) = (body.first(), body.last()) else {
+            return Vec::new();
+        };
+        let range = usize::from(first.start())..usize::from(last.end());
+
+        self.tokens.iter().filter(|(_, offset, _)| range.contains(offset)).map(|(token, offset, _)| (token.clone(), *offset)).collect()
+    }
+
+    fn check_lookups(&mut self, body: &[ast::Stmt]) {
+        let tokens = self.loop_tokens(body);
+        let mut lookups: HashMap<String, (usize, usize)> = HashMap::new();
+
+        for window in tokens.windows(3) {
+            if let [(Tok::Name { name: object }, offset), (Tok::Dot, _), (Tok::Name { name: attribute }, _)] = window {
+                let entry = lookups.entry(format!("{}.{}", object, attribute)).or_insert((0, *offset));
+                entry.0 += 1;
+            }
+        }
+
+        let mut repeated: Vec<usize> = lookups.values().filter(|(count, _)| *count >= REPEATED_LOOKUPS).map(|(_, offset)| *offset).collect();
+        repeated.sort();
+
+        for offset in repeated {
+            self.flag("PERF002", offset, "the same attribute is looked up on every iteration; bind it to a local name before the loop");
+        }
+    }
+
+    fn check_allocations(&mut self, body: &[ast::Stmt]) {
+        let tokens = self.loop_tokens(body);
+
+        let allocations: Vec<usize> = tokens
+            .windows(2)
+            .filter_map(|window| match window {
+                [(previous, _), (Tok::Lsqb, offset)]
+                    if !matches!(previous, Tok::Name { .. } | Tok::Rpar | Tok::Rsqb | Tok::String { .. }) =>
+                {
+                    Some(*offset)
+                }
+                [(Tok::Name { name }, offset), (Tok::Lpar, _)] if name == "list" => Some(*offset),
+                _ => None,
+            })
+            .collect();
+
+        for offset in allocations {
+            self.flag("PERF003", offset, "a new list is allocated every frame; create it once before the loop and reuse it");
+        }
+    }
+
+    fn check_concatenation(&mut self, statement: &ast::Stmt) {
+        let concatenates = match statement {
+            ast::Stmt::AugAssign(assign) => matches!(assign.op, ast::Operator::Add) && is_string_expr(&assign.value),
+            ast::Stmt::Assign(assign) => match (assign.targets.as_slice(), &*assign.value) {
+                ([ast::Expr::Name(target)], ast::Expr::BinOp(binop)) => {
+                    matches!(binop.op, ast::Operator::Add)
+                        && matches!(&*binop.left, ast::Expr::Name(left) if left.id == target.id)
+                        && is_string_expr(&binop.right)
+                }
+                _ => false,
+            },
+            _ => false,
+        };
+
+        if concatenates {
+            self.flag("PERF001", usize::from(statement.start()), "building a string with + in a loop copies it each time; collect the parts in a list and join them");
+        }
+    }
+
+    fn body(&mut self, body: &[ast::Stmt], in_loop: bool) {
+        for statement in body {
+            self.stmt(statement, in_loop);
+        }
+    }
+
+    fn stmt(&mut self, statement: &ast::Stmt, in_loop: bool) {
+        if in_loop {
+            self.check_concatenation(statement);
+        }
+
+        match statement {
+            ast::Stmt::FunctionDef(def) => self.body(&def.body, false),
+            ast::Stmt::AsyncFunctionDef(def) => self.body(&def.body, false),
+            ast::Stmt::ClassDef(def) => self.body(&def.body, false),
+            ast::Stmt::For(block) => {
+                self.check_lookups(&block.body);
+                self.body(&block.body, true);
+                self.body(&block.orelse, in_loop);
+            }
+            ast::Stmt::While(block) => {
+                self.check_lookups(&block.body);
+                self.check_allocations(&block.body);
+                self.body(&block.body, true);
+                self.body(&block.orelse, in_loop);
+            }
+            ast::Stmt::If(block) => {
+                self.body(&block.body, in_loop);
+                self.body(&block.orelse, in_loop);
+            }
+            ast::Stmt::With(block) => self.body(&block.body, in_loop),
+            ast::Stmt::Try(block) => {
+                self.body(&block.body, in_loop);
+                for ast::ExceptHandler::ExceptHandler(handler) in &block.handlers {
+                    self.body(&handler.body, in_loop);
+                }
+                self.body(&block.orelse, in_loop);
+                self.body(&block.finalbody, in_loop);
+            }
+            _ => {}
+        }
+    }
+}
+
+pub fn lint_performance(lines: &[String]) -> Vec<Violation> {
+    let parsed = python::parse_source(lines);
+
+    let mut linter = Linter {
+        lines,
+        line_starts: &parsed.line_starts,
+        tokens: &parsed.tokens,
+        findings: Vec::new(),
+    };
+
+    if let Ok(suite) = &parsed.suite {
+        linter.body(suite, false);
+    }
+
+    linter.findings.sort_by_key(|finding| finding.line_number);
+    linter.findings
+}
@@ -0,0 +1,68 @@
+use std::sync::Arc;
+
+const ALIGNMENT_WINDOW: usize = 64;
+
+#[derive(Clone)]
+pub struct LineOrigin {
+    pub source: Arc<str>,
+    pub line_number: usize,
+}
+
+pub struct BundledLine {
+    pub text: Arc<str>,
+    pub origin: Option<LineOrigin>,
+}
+
+impl BundledLine {
+    pub fn new(text: Arc<str>, source: Arc<str>, line_number: usize) -> Self {
+        BundledLine {
+            text,
+            origin: Some(LineOrigin { source, line_number }),
+        }
+    }
+
+    pub fn generated(text: String) -> Self {
+        BundledLine { text: Arc::from(text), origin: None }
+    }
+}
+
+pub fn align_origins(bundled: &[BundledLine], output_lines: &[String]) -> Vec<Option<LineOrigin>> {
+    let mut origins = Vec::new();
+    let mut next = 0;
+
+    for line in output_lines {
+        let window_size = if line.trim().is_empty() { 1 } else { ALIGNMENT_WINDOW };
+        let window_end = (next + window_size).min(bundled.len());
+        let matched = bundled[next..window_end]
+            .iter()
+            .position(|candidate| candidate.text.trim_end() == line.trim_end());
+
+        match matched {
+            Some(offset) => {
+                origins.push(bundled[next + offset].origin.clone());
+                next += offset + 1;
+            }
+            None => origins.push(None),
+        }
+    }
+
+    origins
+}
+
+pub fn annotate(output_lines: &[String], origins: &[Option<LineOrigin>]) -> Vec<String> {
+    let labels: Vec<String> = origins
+        .iter()
+        .map(|origin| match origin {
+            Some(origin) => format!("{}:{}", origin.source, origin.line_number),
+            None => String::from("<generated>"),
+        })
+        .collect();
+
+    let width = labels.iter().map(|label| label.len()).max().unwrap_or(0);
+
+    labels
+        .iter()
+        .zip(output_lines)
+        .map(|(label, line)| format!("{:<width$} | {}", label, line, width = width))
+        .collect()
+}
@@ -0,0 +1,342 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex, OnceLock};
+use rustpython_parser::ast::{self, Ranged};
+use rustpython_parser::lexer::lex;
+use rustpython_parser::{Mode, Parse, ParseError, Tok};
+use crate::ast_cache;
+
+#[derive(Clone)]
+pub struct ImportedName {
+    pub name: String,
+    pub alias: Option<String>,
+}
+
+impl ImportedName {
+    pub fn bound_name(&self) -> &str {
+        match &self.alias {
+            Some(alias) => alias,
+            None => self.name.split('.').next().unwrap_or(&self.name),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub enum StatementKind {
+    Definition(String),
+    Assignment(Vec<String>),
+    Import(Vec<ImportedName>),
+    ImportFrom { module: String, level: usize, names: Vec<ImportedName> },
+    Other,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Effect {
+    None,
+    Runs,
+    MainGuard,
+}
+
+#[derive(Clone)]
+pub struct FunctionNames {
+    pub body: Vec<String>,
+    pub globals: Vec<String>,
+}
+
+#[derive(Clone)]
+pub struct Statement {
+    pub first_line: usize,
+    pub last_line: usize,
+    pub kind: StatementKind,
+    pub effect: Effect,
+    pub references: Vec<String>,
+    pub function: Option<FunctionNames>,
+}
+
+impl Statement {
+    pub fn bound_names(&self) -> Vec<&str> {
+        match &self.kind {
+            StatementKind::Definition(name) => vec![name.as_str()],
+            StatementKind::Assignment(names) => names.iter().map(|name| name.as_str()).collect(),
+            StatementKind::Import(names) | StatementKind::ImportFrom { names, .. } => {
+                names.iter().map(|name| name.bound_name()).collect()
+            }
+            StatementKind::Other => Vec::new(),
+        }
+    }
+
+    pub fn is_import(&self) -> bool {
+        matches!(self.kind, StatementKind::Import(_) | StatementKind::ImportFrom { .. })
+    }
+}
+
+pub fn line_index(line_starts: &[usize], offset: usize) -> usize {
+    line_starts.partition_point(|start| *start <= offset).saturating_sub(1)
+}
+
+pub struct ParsedSource {
+    pub source: String,
+    pub line_starts: Vec<usize>,
+    pub suite: Result<ast::Suite, ParseError>,
+    pub tokens: Vec<(Tok, usize, usize)>,
+}
+
+impl ParsedSource {
+    pub fn line_index(&self, offset: usize) -> usize {
+        line_index(&self.line_starts, offset)
+    }
+}
+
+const PARSED_SOURCES_KEPT: usize = 16;
+
+pub fn parse_source(lines: &[String]) -> Arc<ParsedSource> {
+    static PARSED: OnceLock<Mutex<HashMap<String, Arc<ParsedSource>>>> = OnceLock::new();
+    let parsed_sources = PARSED.get_or_init(|| Mutex::new(HashMap::new()));
+
+    let source = lines.join("\n");
+    let hash = ast_cache::content_hash(&source);
+
+    if let Some(parsed) = parsed_sources.lock().ok().and_then(|parsed| parsed.get(&hash).cloned()) {
+        return parsed;
+    }
+
+    let mut line_starts = vec![0];
+    line_starts.extend(source.match_indices('\n').map(|(offset, _)| offset + 1));
+
+    let parsed = Arc::new(ParsedSource {
+        suite: ast::Suite::parse(&source, "<source>"),
+        tokens: lex(&source, Mode::Module)
+            .map_while(Result::ok)
+            .map(|(token, range)| (token, usize::from(range.start()), usize::from(range.end())))
+            .collect(),
+        source,
+        line_starts,
+    });
+
+    if let Ok(mut parsed_sources) = parsed_sources.lock() {
+        if parsed_sources.len() >= PARSED_SOURCES_KEPT {
+            parsed_sources.clear();
+        }
+        parsed_sources.insert(hash, parsed.clone());
+    }
+
+    parsed
+}
+
+pub fn child_bodies(statement: &ast::Stmt) -> Vec<&[ast::Stmt]> {
+    match statement {
+        ast::Stmt::FunctionDef(def) => vec![&def.body],
+        ast::Stmt::AsyncFunctionDef(def) => vec![&def.body],
+        ast::Stmt::ClassDef(def) => vec![&def.body],
+        ast::Stmt::For(block) => vec![&block.body, &block.orelse],
+        ast::Stmt::AsyncFor(block) => vec![&block.body, &block.orelse],
+        ast::Stmt::While(block) => vec![&block.body, &block.orelse],
+        ast::Stmt::If(block) => vec![&block.body, &block.orelse],
+        ast::Stmt::With(block) => vec![&block.body],
+        ast::Stmt::AsyncWith(block) => vec![&block.body],
+        ast::Stmt::Match(block) => block.cases.iter().map(|case| case.body.as_slice()).collect(),
+        ast::Stmt::Try(block) => handler_bodies(&block.body, &block.handlers, &block.orelse, &block.finalbody),
+        ast::Stmt::TryStar(block) => handler_bodies(&block.body, &block.handlers, &block.orelse, &block.finalbody),
+        _ => Vec::new(),
+    }
+}
+
+fn handler_bodies<'a>(body: &'a [ast::Stmt], handlers: &'a [ast::ExceptHandler], orelse: &'a [ast::Stmt], finalbody: &'a [ast::Stmt]) -> Vec<&'a [ast::Stmt]> {
+    let mut bodies = vec![body];
+    bodies.extend(handlers.iter().map(|ast::ExceptHandler::ExceptHandler(handler)| handler.body.as_slice()));
+    bodies.extend([orelse, finalbody]);
+    bodies
+}
+
+fn target_names(target: &ast::Expr, names: &mut Vec<String>) {
+    match target {
+        ast::Expr::Name(name) => names.push(name.id.to_string()),
+        ast::Expr::Tuple(tuple) => tuple.elts.iter().for_each(|elt| target_names(elt, names)),
+        ast::Expr::List(list) => list.elts.iter().for_each(|elt| target_names(elt, names)),
+        ast::Expr::Starred(starred) => target_names(&starred.value, names),
+        _ => {}
+    }
+}
+
+fn imported_names(aliases: &[ast::Alias]) -> Vec<ImportedName> {
+    aliases
+        .iter()
+        .map(|alias| ImportedName {
+            name: alias.name.to_string(),
+            alias: alias.asname.as_ref().map(|asname| asname.to_string()),
+        })
+        .collect()
+}
+
+fn statement_kind(statement: &ast::Stmt) -> StatementKind {
+    match statement {
+        ast::Stmt::FunctionDef(def) => StatementKind::Definition(def.name.to_string()),
+        ast::Stmt::AsyncFunctionDef(def) => StatementKind::Definition(def.name.to_string()),
+        ast::Stmt::ClassDef(def) => StatementKind::Definition(def.name.to_string()),
+        ast::Stmt::Assign(assign) => {
+            let mut names = Vec::new();
+            assign.targets.iter().for_each(|target| target_names(target, &mut names));
+            StatementKind::Assignment(names)
+        }
+        ast::Stmt::AnnAssign(assign) if assign.value.is_some() => {
+            let mut names = Vec::new();
+            target_names(&assign.target, &mut names);
+            StatementKind::Assignment(names)
+        }
+        ast::Stmt::Import(import) => StatementKind::Import(imported_names(&import.names)),
+        ast::Stmt::ImportFrom(import) => StatementKind::ImportFrom {
+            module: import.module.as_ref().map(|module| module.to_string()).unwrap_or_default(),
+            level: import.level.as_ref().map(|level| level.to_usize()).unwrap_or(0),
+            names: imported_names(&import.names),
+        },
+        _ => StatementKind::Other,
+    }
+}
+
+fn decorators(statement: &ast::Stmt) -> &[ast::Expr] {
+    match statement {
+        ast::Stmt::FunctionDef(def) => &def.decorator_list,
+        ast::Stmt::AsyncFunctionDef(def) => &def.decorator_list,
+        ast::Stmt::ClassDef(def) => &def.decorator_list,
+        _ => &[],
+    }
+}
+
+fn is_main_guard(statement: &ast::Stmt) -> bool {
+    let ast::Stmt::If(block) = statement else {
+        return false;
+    };
+
+    matches!(&*block.test, ast::Expr::Compare(compare) if matches!(&*compare.left, ast::Expr::Name(name) if name.id.as_str() == "__name__"))
+}
+
+fn effect(statement: &ast::Stmt, has_call: bool) -> Effect {
+    let trivial = match statement {
+        ast::Stmt::FunctionDef(_)
+        | ast::Stmt::AsyncFunctionDef(_)
+        | ast::Stmt::ClassDef(_)
+        | ast::Stmt::Import(_)
+        | ast::Stmt::ImportFrom(_)
+        | ast::Stmt::Global(_)
+        | ast::Stmt::Pass(_) => true,
+        ast::Stmt::Expr(expr) => matches!(&*expr.value, ast::Expr::Constant(constant) if matches!(constant.value, ast::Constant::Str(_))),
+        ast::Stmt::Assign(_) | ast::Stmt::AnnAssign(_) => !has_call,
+        _ => false,
+    };
+
+    match (trivial, is_main_guard(statement)) {
+        (true, _) => Effect::None,
+        (false, true) => Effect::MainGuard,
+        (false, false) => Effect::Runs,
+    }
+}
+
+fn global_names(body: &[ast::Stmt], names: &mut Vec<String>) {
+    for statement in body {
+        if let ast::Stmt::Global(global) = statement {
+            names.extend(global.names.iter().map(|name| name.to_string()));
+        }
+        child_bodies(statement).into_iter().for_each(|child| global_names(child, names));
+    }
+}
+
+fn names_within(names: &[(&str, usize)], range: std::ops::Range<usize>) -> Vec<String> {
+    let mut seen = HashSet::new();
+
+    names
+        .iter()
+        .filter(|(name, offset)| range.contains(offset) && seen.insert(*name))
+        .map(|(name, _)| name.to_string())
+        .collect()
+}
+
+fn function_names(statement: &ast::Stmt, names: &[(&str, usize)]) -> Option<FunctionNames> {
+    let body = match statement {
+        ast::Stmt::FunctionDef(def) => &def.body,
+        ast::Stmt::AsyncFunctionDef(def) => &def.body,
+        _ => return None,
+    };
+    let mut globals = Vec::new();
+    global_names(body, &mut globals);
+
+    Some(FunctionNames {
+        body: body.first().map(|first| names_within(names, usize::from(first.start())..usize::from(statement.end()))).unwrap_or_default(),
+        globals,
+    })
+}
+
+pub fn parse_statements(lines: &[String], path: &str) -> Result<Vec<Statement>, String> {
+    let hash = ast_cache::content_hash(lines.join("\n"));
+
+    if let Some(statements) = ast_cache::lookup(&hash) {
+        return Ok(statements);
+    }
+
+    let parsed = parse_source(lines);
+
+    let suite = parsed.suite.as_ref().map_err(|err| {
+        let line = parsed.line_index(usize::from(err.offset)) + 1;
+        format!("{}:{}: invalid Python syntax: {}", path, line, err.error)
+    })?;
+
+    let mut after_dot = false;
+    let names: Vec<(&str, usize)> = parsed
+        .tokens
+        .iter()
+        .filter_map(|(token, start, _)| {
+            let attribute = after_dot;
+            after_dot = matches!(token, Tok::Dot);
+            match token {
+                Tok::Name { name } if !attribute => Some((name.as_str(), *start)),
+                _ => None,
+            }
+        })
+        .collect();
+    let calls: Vec<usize> = parsed
+        .tokens
+        .windows(2)
+        .filter_map(|window| match window {
+            [(Tok::Name { .. } | Tok::Rpar | Tok::Rsqb, _, _), (Tok::Lpar, offset, _)] => Some(*offset),
+            _ => None,
+        })
+        .collect();
+
+    let statements: Vec<Statement> = suite
+        .iter()
+        .map(|statement| {
+            let start = decorators(statement)
+                .iter()
+                .map(|decorator| usize::from(decorator.start()))
+                .chain([usize::from(statement.start())])
+                .min()
+                .unwrap_or(0);
+            let end = usize::from(statement.end());
+            let statement_range = usize::from(statement.start())..end;
+
+            Statement {
+                first_line: parsed.line_index(start),
+                last_line: parsed.line_index(end.saturating_sub(1).max(start)),
+                kind: statement_kind(statement),
+                effect: effect(statement, calls.iter().any(|offset| statement_range.contains(offset))),
+                references: names_within(&names, start..end),
+                function: function_names(statement, &names),
+            }
+        })
+        .collect();
+
+    ast_cache::store(&hash, &statements);
+
+    Ok(statements)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unterminated_sources_report_a_syntax_error() {
+        let lines = vec![String::from("print(")];
+
+        assert!(parse_statements(&lines, "broken.py").is_err_and(|err| err.starts_with("broken.py:1: invalid Python syntax")));
+        assert!(parse_source(&lines).suite.is_err());
+    }
+}
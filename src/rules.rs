@@ -0,0 +1,157 @@
+use rustpython_parser::ast::{self, Ranged};
+use rustpython_parser::Tok;
+use crate::python;
+
+enum Pattern {
+    Import(&'static [&'static str]),
+    Call(&'static [&'static str], Option<&'static str>),
+}
+
+pub struct Rule {
+    pub id: &'static str,
+    pattern: Pattern,
+    pub message: &'static str,
+}
+
+pub struct Violation {
+    pub rule_id: &'static str,
+    pub line_number: usize,
+    pub line: String,
+    pub message: &'static str,
+}
+
+const EXAM_RULES: &[Rule] = &[
+    Rule {
+        id: "EXAM001",
+        pattern: Pattern::Import(&["ti_hub", "ti_rover"]),
+        message: "TI-Innovator Hub and Rover connectivity is disabled in exam mode",
+    },
+    Rule {
+        id: "EXAM002",
+        pattern: Pattern::Call(&["store_value", "recall_value", "store_list", "recall_list"], Some("ti_system")),
+        message: "stored calculator variables are cleared or locked in exam mode",
+    },
+    Rule {
+        id: "EXAM003",
+        pattern: Pattern::Call(&["open"], None),
+        message: "file access is not permitted in exam mode",
+    },
+];
+
+pub fn ruleset(name: &str) -> Option<&'static [Rule]> {
+    match name {
+        "exam" => Some(EXAM_RULES),
+        _ => None,
+    }
+}
+
+fn collect_imports(body: &[ast::Stmt], imports: &mut Vec<(usize, String)>) {
+    for statement in body {
+        match statement {
+            ast::Stmt::Import(import) => {
+                imports.extend(import.names.iter().map(|alias| (usize::from(statement.start()), alias.name.to_string())));
+            }
+            ast::Stmt::ImportFrom(import) => {
+                if let Some(module) = &import.module {
+                    imports.push((usize::from(statement.start()), module.to_string()));
+                }
+            }
+            _ => {
+                for suite in python::child_bodies(statement) {
+                    collect_imports(suite, imports);
+                }
+            }
+        }
+    }
+}
+
+pub fn check_rules(lines: &[String], rules: &[Rule]) -> Vec<Violation> {
+    let parsed = python::parse_source(lines);
+    let tokens = &parsed.tokens;
+
+    let mut imports = Vec::new();
+    if let Ok(suite) = &parsed.suite {
+        collect_imports(suite, &mut imports);
+    }
+
+    let mut violations: Vec<Violation> = Vec::new();
+
+    for rule in rules {
+        let offsets: Vec<usize> = match rule.pattern {
+            Pattern::Import(modules) => imports
+                .iter()
+                .filter(|(_, module)| modules.contains(&module.split('.').next().unwrap_or("")))
+                .map(|(offset, _)| *offset)
+                .collect(),
+            Pattern::Call(functions, module) => (0..tokens.len())
+                .filter_map(|index| {
+                    let [(Tok::Name { name }, offset, _), (Tok::Lpar, _, _), ..] = &tokens[index..] else {
+                        return None;
+                    };
+
+                    let previous = index.checked_sub(1).map(|previous| &tokens[previous].0);
+                    let qualified = match previous {
+                        Some(Tok::Def | Tok::Class) => false,
+                        Some(Tok::Dot) => index >= 2 && matches!(&tokens[index - 2].0, Tok::Name { name: object } if Some(object.as_str()) == module),
+                        _ => true,
+                    };
+
+                    (qualified && functions.contains(&name.as_str())).then_some(*offset)
+                })
+                .collect(),
+        };
+
+        for offset in offsets {
+            let index = parsed.line_index(offset);
+
+            if violations.iter().any(|violation| violation.rule_id == rule.id && violation.line_number == index + 1) {
+                continue;
+            }
+
+            violations.push(Violation {
+                rule_id: rule.id,
+                line_number: index + 1,
+                line: lines[index].trim().to_string(),
+                message: rule.message,
+            });
+        }
+    }
+
+    violations.sort_by_key(|violation| violation.line_number);
+
+    violations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn violations(source: &str) -> Vec<(&'static str, usize)> {
+        let lines: Vec<String> = source.lines().map(|line| line.to_string()).collect();
+
+        check_rules(&lines, EXAM_RULES)
+            .into_iter()
+            .map(|violation| (violation.rule_id, violation.line_number))
+            .collect()
+    }
+
+    #[test]
+    fn builtin_open_is_flagged_even_as_the_first_token() {
+        assert_eq!(violations("open('scores.txt')\nx = 1\nopen('log.txt')"), vec![("EXAM003", 1), ("EXAM003", 3)]);
+    }
+
+    #[test]
+    fn methods_named_like_flagged_functions_are_ignored() {
+        assert!(violations("f.open('x')\nti_plotlib.open()\ndef open(path):\n    pass\nprint('open(')").is_empty());
+    }
+
+    #[test]
+    fn module_qualified_calls_match_their_module_only() {
+        assert_eq!(violations("import ti_system\nti_system.store_value('a', 1)\nstore_value('b', 2)\ncache.store_value('c', 3)"), vec![("EXAM002", 2), ("EXAM002", 3)]);
+    }
+
+    #[test]
+    fn nested_imports_are_flagged() {
+        assert_eq!(violations("def drive():\n    import ti_rover\n    ti_rover.forward(1)"), vec![("EXAM001", 2)]);
+    }
+}
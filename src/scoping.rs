@@ -0,0 +1,92 @@
+use std::collections::{BTreeSet, HashSet};
+use crate::python::{self, Statement, StatementKind};
+
+fn parse(lines: &[String]) -> Vec<Statement> {
+    python::parse_statements(lines, "<module>").unwrap_or_default()
+}
+
+pub fn module_level_names(lines: &[String]) -> BTreeSet<String> {
+    parse(lines)
+        .iter()
+        .flat_map(|statement| statement.bound_names())
+        .map(|name| name.to_string())
+        .collect()
+}
+
+pub fn referenced_module_state(file: &[String], captured: &[String]) -> BTreeSet<String> {
+    let statements = parse(file);
+    let module_state: HashSet<&str> = statements
+        .iter()
+        .filter(|statement| matches!(statement.kind, StatementKind::Assignment(_)))
+        .flat_map(|statement| statement.bound_names())
+        .collect();
+    let captured_names = module_level_names(captured);
+
+    referenced_names(captured)
+        .into_iter()
+        .filter(|name| module_state.contains(name.as_str()) && !captured_names.contains(name))
+        .collect()
+}
+
+pub fn referenced_definitions(file: &[String], captured: &[String]) -> BTreeSet<String> {
+    let statements = parse(file);
+    let definitions: HashSet<&str> = statements
+        .iter()
+        .filter(|statement| !statement.is_import())
+        .flat_map(|statement| statement.bound_names())
+        .collect();
+    let captured_names = module_level_names(captured);
+
+    referenced_names(captured)
+        .into_iter()
+        .filter(|name| definitions.contains(name.as_str()) && !captured_names.contains(name))
+        .collect()
+}
+
+pub fn required_imports(file: &[String], captured: &[String]) -> Vec<String> {
+    let referenced = referenced_names(captured);
+
+    parse(file)
+        .iter()
+        .filter(|statement| statement.is_import())
+        .filter(|statement| statement.bound_names().iter().any(|name| referenced.contains(*name)))
+        .flat_map(|statement| file[statement.first_line..=statement.last_line].iter().cloned())
+        .collect()
+}
+
+pub fn referenced_names(lines: &[String]) -> HashSet<String> {
+    parse(lines).into_iter().flat_map(|statement| statement.references).collect()
+}
+
+pub fn check_global_references(file: &[String], captured: &[String], imported: &HashSet<String>) -> Vec<String> {
+    let module_names = module_level_names(file);
+    let captured_statements = parse(captured);
+    let captured_names: BTreeSet<&str> = captured_statements.iter().flat_map(|statement| statement.bound_names()).collect();
+
+    let mut warnings = Vec::new();
+    let mut reported = HashSet::new();
+
+    for statement in &captured_statements {
+        let (StatementKind::Definition(function), Some(names)) = (&statement.kind, &statement.function) else {
+            continue;
+        };
+
+        for name in &names.body {
+            if names.globals.contains(name) && imported.contains(name) && reported.insert(format!("shared {}", name)) {
+                warnings.push(format!(
+                    "{}() rebinds {} via `global`; the importer used to hold its own copy but now sees every update",
+                    function, name
+                ));
+            }
+
+            if module_names.contains(name) && !captured_names.contains(name.as_str()) && reported.insert(name.clone()) {
+                warnings.push(format!(
+                    "{}() uses module global {}, which is not inlined; import {} as well",
+                    function, name, name
+                ));
+            }
+        }
+    }
+
+    warnings
+}
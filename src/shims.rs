@@ -0,0 +1,95 @@
+use rustpython_parser::ast;
+use crate::python;
+
+const SHIM_PRELUDE: &[&str] = &[
+    "import sys",
+    "class _TiShim:",
+    "    def __init__(self, name, functions):",
+    "        self.__name__ = name",
+    "        self.__dict__.update(functions)",
+    "def _shim_noop(*args, **kwargs):",
+    "    return None",
+];
+
+const SHIMS: &[(&str, &[&str])] = &[
+    (
+        "ti_system",
+        &[
+            "_shim_values = {}",
+            "_shim_escapes = [0]",
+            "def _shim_escape():",
+            "    _shim_escapes[0] += 1",
+            "    return _shim_escapes[0] > 100",
+            "sys.modules[\"ti_system\"] = _TiShim(\"ti_system\", {",
+            "    \"disp_clr\": _shim_noop, \"disp_wait\": _shim_noop, \"disp_cursor\": _shim_noop,",
+            "    \"disp_at\": lambda row, text, align=\"left\": print(text),",
+            "    \"wait_key\": lambda: 0, \"get_key\": lambda: 0, \"escape\": _shim_escape,",
+            "    \"store_value\": lambda name, value: _shim_values.__setitem__(name, value),",
+            "    \"recall_value\": lambda name: _shim_values.get(name, 0),",
+            "    \"store_list\": lambda name, values: _shim_values.__setitem__(name, list(values)),",
+            "    \"recall_list\": lambda name: _shim_values.get(name, []),",
+            "})",
+        ],
+    ),
+    (
+        "ti_plotlib",
+        &[
+            "sys.modules[\"ti_plotlib\"] = _TiShim(\"ti_plotlib\", {name: _shim_noop for name in (",
+            "    \"cls\", \"window\", \"auto_window\", \"grid\", \"axes\", \"labels\", \"title\", \"color\", \"pen\",",
+            "    \"plot\", \"line\", \"lin_reg\", \"scatter\", \"text_at\", \"show_plot\",",
+            ")})",
+        ],
+    ),
+    (
+        "ti_draw",
+        &[
+            "sys.modules[\"ti_draw\"] = _TiShim(\"ti_draw\", dict({name: _shim_noop for name in (",
+            "    \"clear\", \"clear_rect\", \"set_color\", \"set_pen\", \"set_window\", \"draw_line\", \"draw_rect\",",
+            "    \"fill_rect\", \"draw_circle\", \"fill_circle\", \"draw_arc\", \"fill_arc\", \"draw_poly\", \"fill_poly\",",
+            "    \"draw_text\", \"plot_xy\", \"use_buffer\", \"paint_buffer\", \"show_draw\",",
+            ")}, get_screen_dim=lambda: (320, 240)))",
+        ],
+    ),
+];
+
+fn imported_modules(body: &[ast::Stmt], modules: &mut Vec<String>) {
+    for statement in body {
+        match statement {
+            ast::Stmt::Import(import) => modules.extend(import.names.iter().map(|alias| alias.name.split('.').next().unwrap_or("").to_string())),
+            ast::Stmt::ImportFrom(import) => modules.extend(import.module.as_ref().map(|module| module.split('.').next().unwrap_or("").to_string())),
+            _ => {
+                for suite in python::child_bodies(statement) {
+                    imported_modules(suite, modules);
+                }
+            }
+        }
+    }
+}
+
+pub fn inject_shims(lines: Vec<String>) -> Vec<String> {
+    let mut modules = Vec::new();
+    if let Ok(suite) = &python::parse_source(&lines).suite {
+        imported_modules(suite, &mut modules);
+    }
+
+    let imported: Vec<&str> = SHIMS
+        .iter()
+        .map(|(module, _)| *module)
+        .filter(|module| modules.iter().any(|imported| imported == module))
+        .collect();
+
+    if imported.is_empty() {
+        return lines;
+    }
+
+    let mut shimmed_lines: Vec<String> = SHIM_PRELUDE.iter().map(|line| line.to_string()).collect();
+
+    for (_, shim) in SHIMS.iter().filter(|(module, _)| imported.contains(module)) {
+        shimmed_lines.extend(shim.iter().map(|line| line.to_string()));
+    }
+
+    shimmed_lines.push(String::new());
+    shimmed_lines.extend(lines);
+
+    shimmed_lines
+}
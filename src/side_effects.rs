@@ -0,0 +1,23 @@
+use crate::python::{self, Effect};
+
+pub struct SideEffect {
+    pub index: usize,
+    pub statement: String,
+    pub is_main_guard: bool,
+}
+
+pub fn find_module_level_side_effects(lines: &[String]) -> Vec<SideEffect> {
+    let Ok(statements) = python::parse_statements(lines, "<module>") else {
+        return Vec::new();
+    };
+
+    statements
+        .iter()
+        .filter(|statement| statement.effect != Effect::None)
+        .map(|statement| SideEffect {
+            index: statement.first_line,
+            statement: lines[statement.first_line].trim().to_string(),
+            is_main_guard: statement.effect == Effect::MainGuard,
+        })
+        .collect()
+}
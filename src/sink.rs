@@ -0,0 +1,277 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{Cursor, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+use base64::{Engine as _, engine::general_purpose};
+use sha2::{Digest, Sha256};
+use zip::write::{FileOptions, ZipWriter};
+use zip::ZipArchive;
+use crate::ast_cache::write_atomically;
+use crate::{appvar, archive, clipboard};
+use crate::FileObject;
+
+#[derive(Clone)]
+pub struct EntryMetadata {
+    pub permissions: u32,
+    pub modified: Option<u64>,
+    pub comment: Option<String>,
+}
+
+pub struct Artifact {
+    pub file_name: String,
+    pub bytes: Vec<u8>,
+    pub metadata: EntryMetadata,
+}
+
+pub trait OutputSink {
+    fn emit(&self, artifacts: Vec<Artifact>) -> Result<(), String>;
+}
+
+pub fn encode_artifacts(files: Vec<FileObject>, format: &str, metadata: &EntryMetadata) -> Result<Vec<Artifact>, String> {
+    files
+        .into_iter()
+        .map(|file| {
+            let source = file.contents.join("\n");
+
+            match format {
+                "8xv" => Ok(Artifact {
+                    file_name: format!("{}.8xv", file.script_name),
+                    bytes: appvar::create_8xv(&file.script_name, &source)?,
+                    metadata: metadata.clone(),
+                }),
+                _ => Ok(Artifact {
+                    file_name: format!("{}.py", file.script_name),
+                    bytes: source.into_bytes(),
+                    metadata: metadata.clone(),
+                }),
+            }
+        })
+        .collect()
+}
+
+#[derive(Default)]
+pub struct SinkRegistry {
+    sinks: BTreeMap<String, Box<dyn OutputSink>>,
+}
+
+impl SinkRegistry {
+    pub fn with_builtin_sinks() -> Self {
+        let mut registry = SinkRegistry::default();
+        registry.register("zip", Box::new(ZipSink));
+        registry.register("clipboard", Box::new(ClipboardSink));
+        registry
+    }
+
+    pub fn register(&mut self, name: &str, sink: Box<dyn OutputSink>) {
+        self.sinks.insert(name.to_string(), sink);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn OutputSink> {
+        self.sinks.get(name).map(|sink| sink.as_ref())
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.sinks.keys().map(|name| name.as_str()).collect()
+    }
+}
+
+pub fn content_hash(artifacts: &[Artifact]) -> String {
+    let mut hasher = Sha256::new();
+
+    for artifact in artifacts {
+        hasher.update(artifact.file_name.as_bytes());
+        hasher.update([0]);
+        hasher.update(&artifact.bytes);
+    }
+
+    hasher.finalize().iter().take(3).map(|byte| format!("{:02x}", byte)).collect()
+}
+
+pub fn record_provenance(artifacts: &mut [Artifact], revision: Option<&str>) {
+    for artifact in artifacts.iter_mut() {
+        let digest: String = Sha256::digest(&artifact.bytes).iter().map(|byte| format!("{:02x}", byte)).collect();
+
+        let provenance = format!(
+            "ti-84-python-compiler {}; source {}; sha256 {}",
+            env!("CARGO_PKG_VERSION"),
+            revision.unwrap_or("unknown"),
+            digest
+        );
+
+        artifact.metadata.comment = Some(match &artifact.metadata.comment {
+            Some(comment) => format!("{}; {}", comment, provenance),
+            None => provenance,
+        });
+    }
+}
+
+pub fn with_hash_suffix(file_name: &str, hash: &str) -> String {
+    match file_name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => format!("{}-{}.{}", stem, hash, extension),
+        _ => format!("{}-{}", file_name, hash),
+    }
+}
+
+pub fn hash_artifact_names(artifacts: &mut [Artifact]) {
+    for artifact in artifacts.iter_mut() {
+        let hash = content_hash(std::slice::from_ref(artifact));
+        artifact.file_name = with_hash_suffix(&artifact.file_name, &hash);
+    }
+}
+
+pub struct ZipSink;
+
+impl OutputSink for ZipSink {
+    fn emit(&self, artifacts: Vec<Artifact>) -> Result<(), String> {
+        let zip_content = create_zip(artifacts)?;
+
+        println!("{}", general_purpose::STANDARD.encode(&zip_content));
+
+        Ok(())
+    }
+}
+
+pub struct ClipboardSink;
+
+impl OutputSink for ClipboardSink {
+    fn emit(&self, artifacts: Vec<Artifact>) -> Result<(), String> {
+        let file_count = artifacts.len();
+        let encoded = general_purpose::STANDARD.encode(create_zip(artifacts)?);
+
+        if !clipboard::available() {
+            println!("{}", encoded);
+            return Ok(());
+        }
+
+        clipboard::copy(&encoded)?;
+
+        let message = format!("copied {} file(s) to the clipboard as {} bytes of base64", file_count, encoded.len());
+        clipboard::notify("TI-84 bundle ready", &message);
+        eprintln!("{}", message);
+
+        Ok(())
+    }
+}
+
+pub struct ZipFileSink {
+    pub path: PathBuf,
+}
+
+impl OutputSink for ZipFileSink {
+    fn emit(&self, artifacts: Vec<Artifact>) -> Result<(), String> {
+        let zip_content = create_zip(artifacts)?;
+
+        if let Some(parent) = self.path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            fs::create_dir_all(parent).map_err(|err| format!("Failed to create {}: {}", parent.display(), err))?;
+        }
+
+        write_atomically(&self.path, &zip_content).map_err(|err| format!("Failed to write {}: {}", self.path.display(), err))?;
+
+        eprintln!("wrote {} ({} bytes)", self.path.display(), zip_content.len());
+
+        Ok(())
+    }
+}
+
+pub struct DirectorySink {
+    pub path: PathBuf,
+}
+
+impl OutputSink for DirectorySink {
+    fn emit(&self, artifacts: Vec<Artifact>) -> Result<(), String> {
+        fs::create_dir_all(&self.path).map_err(|err| format!("Failed to create {}: {}", self.path.display(), err))?;
+
+        for artifact in artifacts {
+            let file_path = self.path.join(&artifact.file_name);
+
+            fs::write(&file_path, &artifact.bytes).map_err(|err| format!("Failed to write {}: {}", file_path.display(), err))?;
+            apply_file_metadata(&file_path, &artifact.metadata).map_err(|err| format!("Failed to set metadata on {}: {}", file_path.display(), err))?;
+
+            eprintln!("wrote {}", file_path.display());
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(unix)]
+fn apply_file_metadata(path: &Path, metadata: &EntryMetadata) -> std::io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    fs::set_permissions(path, fs::Permissions::from_mode(metadata.permissions))?;
+
+    if let Some(modified) = metadata.modified {
+        File::options().write(true).open(path)?.set_modified(UNIX_EPOCH + Duration::from_secs(modified))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn apply_file_metadata(path: &Path, metadata: &EntryMetadata) -> std::io::Result<()> {
+    if let Some(modified) = metadata.modified {
+        File::options().write(true).open(path)?.set_modified(UNIX_EPOCH + Duration::from_secs(modified))?;
+    }
+
+    Ok(())
+}
+
+const ZIP64_ENTRY_BYTES: usize = u32::MAX as usize;
+
+pub fn create_zip(artifacts: Vec<Artifact>) -> Result<Vec<u8>, String> {
+    let mut buffer = Cursor::new(Vec::new());
+
+    let mut zip = ZipWriter::new(&mut buffer);
+
+    let entry_count = artifacts.len();
+    let mut entry_names = HashSet::new();
+    let mut comments = HashMap::new();
+
+    for artifact in artifacts {
+        if !entry_names.insert(artifact.file_name.clone()) {
+            return Err(format!("Cannot add {} to zip: another bundle already uses that name", artifact.file_name));
+        }
+
+        if artifact.file_name.len() > u16::MAX as usize {
+            let prefix: String = artifact.file_name.chars().take(32).collect();
+            return Err(format!("Cannot add {}... to zip: entry names are limited to {} bytes", prefix, u16::MAX));
+        }
+
+        let mut entry_options: FileOptions<()> = FileOptions::default()
+            .compression_method(zip::CompressionMethod::Stored)
+            .unix_permissions(artifact.metadata.permissions)
+            .large_file(artifact.bytes.len() >= ZIP64_ENTRY_BYTES);
+
+        if let Some(modified) = artifact.metadata.modified {
+            entry_options = entry_options.last_modified_time(archive::zip_datetime(modified)?);
+        }
+
+        if let Some(comment) = &artifact.metadata.comment {
+            comments.insert(artifact.file_name.clone(), comment.clone());
+        }
+
+        zip.start_file(artifact.file_name.as_str(), entry_options)
+            .map_err(|err| format!("Failed to add {} to zip: {}", artifact.file_name, err))?;
+
+        zip.write_all(&artifact.bytes)
+            .map_err(|err| format!("Failed to write {} to zip: {}", artifact.file_name, err))?;
+    }
+
+    zip.finish().map_err(|err| format!("Failed to finish zip: {}", err))?;
+
+    let mut zip_content = buffer.into_inner();
+
+    if !comments.is_empty() {
+        zip_content = archive::write_entry_comments(zip_content, &comments)?;
+    }
+
+    let written = ZipArchive::new(Cursor::new(&zip_content))
+        .map_err(|err| format!("Refusing to emit a corrupt zip: {}", err))?;
+
+    if written.len() != entry_count {
+        return Err(format!("Refusing to emit a corrupt zip: wrote {} of {} entries", written.len(), entry_count));
+    }
+
+    Ok(zip_content)
+}
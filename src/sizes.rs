@@ -0,0 +1,186 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt;
+use std::fs;
+use crate::graph::json_string;
+use crate::provenance::{self, BundledLine};
+use crate::python::{self, StatementKind};
+
+pub struct SymbolSize {
+    pub name: String,
+    pub source: Option<String>,
+    pub lines: usize,
+    pub bytes: usize,
+}
+
+pub struct ScriptSize {
+    pub script_name: String,
+    pub bytes: usize,
+    pub symbols: Vec<SymbolSize>,
+}
+
+impl ScriptSize {
+    pub fn measure(script_name: &str, bundled_lines: &[BundledLine], output_lines: &[String]) -> Self {
+        let origins = provenance::align_origins(bundled_lines, output_lines);
+        let statements = python::parse_statements(output_lines, script_name).unwrap_or_default();
+
+        let mut symbols: Vec<SymbolSize> = Vec::new();
+
+        if let Some(first) = statements.first().filter(|first| first.first_line > 0) {
+            let header = &output_lines[..first.first_line];
+            symbols.push(SymbolSize {
+                name: String::from("(comments)"),
+                source: None,
+                lines: header.len(),
+                bytes: header.iter().map(|line| line.len() + 1).sum(),
+            });
+        }
+
+        for (index, statement) in statements.iter().enumerate() {
+            let last_line = statements.get(index + 1).map(|next| next.first_line).unwrap_or(output_lines.len());
+            let lines = &output_lines[statement.first_line..last_line.max(statement.first_line + 1).min(output_lines.len())];
+
+            let name = match &statement.kind {
+                StatementKind::Definition(_) | StatementKind::Assignment(_) => statement.bound_names().join(", "),
+                StatementKind::Import(_) | StatementKind::ImportFrom { .. } => String::from("(imports)"),
+                StatementKind::Other => String::from("(top-level code)"),
+            };
+            let source = origins
+                .get(statement.first_line)
+                .and_then(|origin| origin.as_ref())
+                .map(|origin| origin.source.to_string());
+            let bytes = lines.iter().map(|line| line.len() + 1).sum();
+
+            match symbols.iter_mut().find(|symbol| symbol.name == name && symbol.source == source) {
+                Some(symbol) => {
+                    symbol.lines += lines.len();
+                    symbol.bytes += bytes;
+                }
+                None => symbols.push(SymbolSize { name, source, lines: lines.len(), bytes }),
+            }
+        }
+
+        symbols.sort_by(|first, second| second.bytes.cmp(&first.bytes).then_with(|| first.name.cmp(&second.name)));
+
+        ScriptSize {
+            script_name: script_name.to_string(),
+            bytes: output_lines.join("\n").len(),
+            symbols,
+        }
+    }
+}
+
+pub fn render_text(sizes: &[ScriptSize]) -> String {
+    let mut lines = Vec::new();
+
+    for size in sizes {
+        lines.push(format!("{}.py: {} bytes", size.script_name, size.bytes));
+
+        let width = size.symbols.iter().map(|symbol| symbol.bytes.to_string().len()).max().unwrap_or(0);
+
+        for symbol in &size.symbols {
+            lines.push(format!(
+                "  {:>width$}  {}: {}",
+                symbol.bytes,
+                symbol.source.as_deref().unwrap_or("<generated>"),
+                symbol.name,
+                width = width
+            ));
+        }
+    }
+
+    lines.push(format!("total: {} bytes", sizes.iter().map(|size| size.bytes).sum::<usize>()));
+    lines.join("\n")
+}
+
+pub fn render_json(sizes: &[ScriptSize]) -> String {
+    let scripts: Vec<String> = sizes
+        .iter()
+        .map(|size| {
+            let symbols: Vec<String> = size
+                .symbols
+                .iter()
+                .map(|symbol| {
+                    format!(
+                        "{{\"name\":{},\"source\":{},\"lines\":{},\"bytes\":{}}}",
+                        json_string(&symbol.name),
+                        symbol.source.as_deref().map(json_string).unwrap_or_else(|| String::from("null")),
+                        symbol.lines,
+                        symbol.bytes
+                    )
+                })
+                .collect();
+
+            format!(
+                "{{\"name\":{},\"bytes\":{},\"symbols\":[{}]}}",
+                json_string(&size.script_name),
+                size.bytes,
+                symbols.join(",")
+            )
+        })
+        .collect();
+
+    format!(
+        "{{\"scripts\":[{}],\"total\":{}}}",
+        scripts.join(","),
+        sizes.iter().map(|size| size.bytes).sum::<usize>()
+    )
+}
+
+#[derive(Clone, Copy)]
+pub enum Growth {
+    Bytes(usize),
+    Percent(f64),
+}
+
+impl Growth {
+    pub fn exceeded(&self, previous: usize, current: usize) -> bool {
+        let growth = current.saturating_sub(previous);
+
+        match self {
+            Growth::Bytes(bytes) => growth > *bytes,
+            Growth::Percent(percent) => growth as f64 > previous as f64 * percent / 100.0,
+        }
+    }
+}
+
+impl fmt::Display for Growth {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Growth::Bytes(bytes) => write!(f, "{} bytes", bytes),
+            Growth::Percent(percent) => write!(f, "{}%", percent),
+        }
+    }
+}
+
+pub fn parse_growth(value: &str) -> Result<Growth, String> {
+    let invalid = || format!("{} is not a byte count such as 2048 or a percentage such as 10%", value);
+
+    match value.strip_suffix('%') {
+        Some(percent) => percent.trim().parse().ok().filter(|percent: &f64| *percent >= 0.0).map(Growth::Percent).ok_or_else(invalid),
+        None => value.trim().parse().map(Growth::Bytes).map_err(|_| invalid()),
+    }
+}
+
+fn record_path() -> String {
+    env::var("SIZE_RECORD").ok().filter(|path| !path.is_empty()).unwrap_or_else(|| String::from(".bundle-sizes"))
+}
+
+pub fn read_record() -> BTreeMap<String, usize> {
+    fs::read_to_string(record_path())
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| {
+            let (name, bytes) = line.split_once('\t')?;
+            Some((name.to_string(), bytes.parse().ok()?))
+        })
+        .collect()
+}
+
+pub fn write_record(record: &BTreeMap<String, usize>) {
+    let lines: Vec<String> = record.iter().map(|(name, bytes)| format!("{}\t{}", name, bytes)).collect();
+
+    if let Err(err) = fs::write(record_path(), lines.join("\n") + "\n") {
+        eprintln!("warning: could not record bundle sizes in {}: {}", record_path(), err);
+    }
+}
@@ -0,0 +1,53 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+use crate::ast_cache::write_atomically;
+
+pub fn read_snapshots(directory: &Path) -> BTreeMap<PathBuf, String> {
+    WalkDir::new(directory)
+        .into_iter()
+        .flatten()
+        .filter(|entry| entry.file_type().is_file() && entry.path().extension().is_some_and(|extension| extension == "py"))
+        .filter_map(|entry| {
+            let relative = entry.path().strip_prefix(directory).ok()?.to_path_buf();
+            Some((relative, fs::read_to_string(entry.path()).ok()?))
+        })
+        .collect()
+}
+
+pub fn write_snapshot(path: &Path, contents: &str) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|err| format!("Failed to create {}: {}", parent.display(), err))?;
+    }
+
+    write_atomically(path, contents).map_err(|err| format!("Failed to write {}: {}", path.display(), err))
+}
+
+pub fn first_difference(expected: &str, actual: &str) -> Option<(usize, String, String)> {
+    if expected == actual {
+        return None;
+    }
+
+    let expected_lines: Vec<&str> = expected.split('\n').collect();
+    let actual_lines: Vec<&str> = actual.split('\n').collect();
+
+    (0..expected_lines.len().max(actual_lines.len()))
+        .find(|index| expected_lines.get(*index) != actual_lines.get(*index))
+        .map(|index| {
+            let line = |lines: &[&str]| lines.get(index).map_or(String::from("(end of file)"), |line| line.to_string());
+            (index + 1, line(&expected_lines), line(&actual_lines))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_difference_points_at_the_changed_line() {
+        assert_eq!(first_difference("a\nb", "a\nb"), None);
+        assert_eq!(first_difference("a\nb\nc", "a\nB\nc"), Some((2, String::from("b"), String::from("B"))));
+        assert_eq!(first_difference("a", "a\nb"), Some((2, String::from("(end of file)"), String::from("b"))));
+    }
+}
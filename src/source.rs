@@ -0,0 +1,765 @@
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
+use std::thread;
+use std::time::{Duration, Instant, UNIX_EPOCH};
+use regex::Regex;
+use reqwest::blocking::{Client, RequestBuilder, Response};
+use reqwest::redirect::Policy;
+use reqwest::Url;
+use crate::fetch_cache::FetchCache;
+use crate::listing_cache::ListingCache;
+use crate::suggest;
+use crate::tools::{self, MissingTool};
+
+pub trait Source: Sync {
+    fn root(&self) -> &str;
+
+    fn read_lines(&self, path: &str) -> Result<Vec<String>, String>;
+
+    fn read_lines_within(&self, path: &str, _timeout: Duration) -> Result<Vec<String>, String> {
+        self.read_lines(path)
+    }
+
+    fn check_within_root(&self, path: &str) -> Result<(), String>;
+
+    fn list_directory(&self, path: &str) -> Option<Vec<String>>;
+
+    fn revision(&self) -> Option<String>;
+
+    fn fingerprint(&self, path: &str) -> Option<String>;
+}
+
+pub fn missing_tools(root: &str) -> Vec<MissingTool> {
+    if root.starts_with("http://") || root.starts_with("https://") || env::var("SOURCE_REVISION").is_ok() || tools::on_path("git") {
+        return Vec::new();
+    }
+
+    vec![MissingTool {
+        name: String::from("git"),
+        skipped: String::from("bundles will record their source revision as unknown"),
+        hint: String::from("install git from git-scm.com or set SOURCE_REVISION"),
+    }]
+}
+
+pub fn from_root(root: &str, refresh_listings: bool, fail_fast: bool) -> Box<dyn Source> {
+    let root = root.trim_end_matches('/');
+
+    if root.starts_with("http://") || root.starts_with("https://") {
+        let allowlist = fetch_allowlist();
+
+        return Box::new(HttpSource {
+            root: root.to_string(),
+            client: http_client(allowlist.clone()),
+            allowlist,
+            listing_cache: ListingCache::from_env(refresh_listings),
+            fetch_cache: FetchCache::from_env(),
+            credentials: fetch_credentials(),
+            retries: if fail_fast { 0 } else { env_number("FETCH_RETRIES", 3) },
+        });
+    }
+
+    match fs::canonicalize(root) {
+        Ok(canonical_root) => Box::new(FsSource {
+            root: root.to_string(),
+            canonical_root,
+        }),
+        Err(err) => {
+            eprintln!("ROOT_DIRECTORY is neither an http(s) URL nor a readable directory ({}): {}", err, root);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn max_source_bytes() -> usize {
+    env::var("MAX_SOURCE_BYTES")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(512 * 1024)
+}
+
+fn env_number(name: &str, default: u64) -> u64 {
+    env::var(name).ok().and_then(|value| value.parse().ok()).unwrap_or(default)
+}
+
+fn is_transient(result: &reqwest::Result<Response>) -> bool {
+    match result {
+        Ok(response) => response.status().is_server_error() || response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS,
+        Err(err) => err.is_timeout() || err.is_connect(),
+    }
+}
+
+fn send_with_retries(request: RequestBuilder, retries: u64, deadline: Option<Instant>) -> (reqwest::Result<Response>, u64) {
+    let mut delay = Duration::from_millis(env_number("FETCH_RETRY_DELAY_MS", 500));
+    let mut attempts = 1;
+
+    loop {
+        let Some(mut attempt) = request.try_clone() else {
+            return (request.send(), attempts);
+        };
+
+        if let Some(deadline) = deadline {
+            attempt = attempt.timeout(deadline.saturating_duration_since(Instant::now()));
+        }
+
+        let result = attempt.send();
+
+        if attempts > retries || !is_transient(&result) || deadline.is_some_and(|deadline| Instant::now() + delay >= deadline) {
+            return (result, attempts);
+        }
+
+        thread::sleep(delay);
+        delay *= 2;
+        attempts += 1;
+    }
+}
+
+fn attempts_note(attempts: u64) -> String {
+    if attempts > 1 {
+        format!(" after {} attempts", attempts)
+    } else {
+        String::new()
+    }
+}
+
+fn fetch_allowlist() -> Option<Vec<String>> {
+    let entries: Vec<String> = env::var("FETCH_ALLOWLIST")
+        .ok()?
+        .split(',')
+        .map(|entry| entry.trim().trim_end_matches('/').to_string())
+        .filter(|entry| !entry.is_empty())
+        .collect();
+
+    Some(entries)
+}
+
+enum Credentials {
+    Bearer(String),
+    Basic(String, Option<String>),
+}
+
+fn fetch_credentials() -> Option<Credentials> {
+    let non_empty = |name| env::var(name).ok().filter(|value: &String| !value.is_empty());
+
+    if let Some(token) = non_empty("FETCH_TOKEN").or_else(|| non_empty("GITHUB_TOKEN")) {
+        return Some(Credentials::Bearer(token));
+    }
+
+    non_empty("FETCH_USERNAME").map(|username| Credentials::Basic(username, non_empty("FETCH_PASSWORD")))
+}
+
+fn is_allowed(url: &Url, allowlist: &[String]) -> bool {
+    let host = url.host_str().unwrap_or("");
+    let host_and_port = match url.port() {
+        Some(port) => format!("{}:{}", host, port),
+        None => host.to_string(),
+    };
+
+    allowlist.iter().any(|entry| {
+        if entry.starts_with("http://") || entry.starts_with("https://") {
+            let url = url.as_str();
+            url == entry || url.starts_with(&format!("{}/", entry)) || url.starts_with(&format!("{}?", entry))
+        } else {
+            entry.eq_ignore_ascii_case(host) || entry.eq_ignore_ascii_case(&host_and_port)
+        }
+    })
+}
+
+fn describe_error(err: &dyn Error) -> String {
+    let mut message = err.to_string();
+    let mut source = err.source();
+
+    while let Some(cause) = source {
+        message.push_str(&format!(": {}", cause));
+        source = cause.source();
+    }
+
+    message
+}
+
+fn not_found(source: &dyn Source, path: &str) -> String {
+    suggest::diagnose_missing(path, source).join("\n")
+}
+
+fn source_lines(path: &str, bytes: &[u8]) -> Result<Vec<String>, String> {
+    let content = String::from_utf8_lossy(bytes);
+    let leading = content.trim_start().chars().take(15).collect::<String>().to_ascii_lowercase();
+
+    if leading.starts_with("<!doctype html") || leading.starts_with("<html") || content.contains('\0') {
+        return Err(format!("Refusing to bundle {}: response is not Python source", path));
+    }
+
+    Ok(content.lines().map(|line| line.to_string()).collect())
+}
+
+fn http_client(allowlist: Option<Vec<String>>) -> Client {
+    let mut builder = Client::builder()
+        .gzip(true)
+        .brotli(true)
+        .http2_adaptive_window(true)
+        .timeout(Duration::from_secs(env_number("FETCH_TIMEOUT", 30)));
+
+    if let Some(allowlist) = allowlist {
+        builder = builder.redirect(Policy::custom(move |attempt| {
+            if !is_allowed(attempt.url(), &allowlist) {
+                let message = format!("redirect to {} is not covered by FETCH_ALLOWLIST", attempt.url());
+                attempt.error(message)
+            } else if attempt.previous().len() >= 10 {
+                attempt.error("too many redirects")
+            } else {
+                attempt.follow()
+            }
+        }));
+    }
+
+    builder.build().expect("failed to build HTTP client")
+}
+
+pub struct HttpSource {
+    root: String,
+    allowlist: Option<Vec<String>>,
+    client: Client,
+    listing_cache: Option<ListingCache>,
+    fetch_cache: Option<FetchCache>,
+    credentials: Option<Credentials>,
+    retries: u64,
+}
+
+impl HttpSource {
+    fn request(&self, method: reqwest::Method, url: &str) -> RequestBuilder {
+        let mut request = self.client.request(method, url).header(reqwest::header::USER_AGENT, "ti-84-python-compiler");
+
+        let Ok(parsed) = Url::parse(url) else {
+            return request;
+        };
+        let root_host = Url::parse(&self.root).ok().and_then(|root| root.host_str().map(|host| host.to_string()));
+        let github_api = parsed.host_str() == Some("api.github.com") && root_host.as_deref() == Some("raw.githubusercontent.com");
+
+        match parsed.host_str() {
+            Some("api.github.com") => request = request.header(reqwest::header::ACCEPT, "application/vnd.github+json"),
+            Some("raw.githubusercontent.com") => request = request.header(reqwest::header::ACCEPT, "application/vnd.github.raw"),
+            _ => {}
+        }
+
+        if parsed.host_str() != root_host.as_deref() && !github_api {
+            return request;
+        }
+
+        match &self.credentials {
+            Some(Credentials::Bearer(token)) => request.bearer_auth(token),
+            Some(Credentials::Basic(username, password)) => request.basic_auth(username, password.as_deref()),
+            None => request,
+        }
+    }
+
+    fn check_allowed(&self, url: &str) -> Result<(), String> {
+        let Some(allowlist) = &self.allowlist else {
+            return Ok(());
+        };
+
+        let parsed = Url::parse(url).map_err(|err| format!("Invalid source URL ({}): {}", err, url))?;
+
+        if !is_allowed(&parsed, allowlist) {
+            return Err(format!("Refusing to fetch {}: it is not covered by FETCH_ALLOWLIST", url));
+        }
+
+        Ok(())
+    }
+
+    fn github_contents_url(directory_url: &str) -> Option<String> {
+        let path = directory_url.strip_prefix("https://raw.githubusercontent.com/")?;
+        let mut parts = path.trim_end_matches('/').splitn(4, '/');
+
+        let owner = parts.next()?;
+        let repo = parts.next()?;
+        let reference = parts.next()?;
+        let directory = parts.next().unwrap_or("");
+
+        Some(format!("https://api.github.com/repos/{}/{}/contents/{}?ref={}", owner, repo, directory, reference))
+    }
+
+    fn fetch_lines(&self, url: &str, timeout: Option<Duration>) -> Result<Vec<String>, String> {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+
+        self.check_allowed(url)?;
+
+        let max_source_bytes = max_source_bytes();
+
+        let cached = self.fetch_cache.as_ref().and_then(|cache| cache.get(url));
+
+        let mut request = self.request(reqwest::Method::GET, url);
+
+        if let Some(etag) = cached.as_ref().and_then(|cached| cached.etag.as_deref()) {
+            request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+        }
+
+        if let Some(last_modified) = cached.as_ref().and_then(|cached| cached.last_modified.as_deref()) {
+            request = request.header(reqwest::header::IF_MODIFIED_SINCE, last_modified);
+        }
+
+        let (response, attempts) = send_with_retries(request, self.retries, deadline);
+        let response = response.map_err(|err| format!("Failed to fetch {}{}: {}", url, attempts_note(attempts), describe_error(&err)))?;
+
+        if let (reqwest::StatusCode::NOT_MODIFIED, Some(cached)) = (response.status(), &cached) {
+            return source_lines(url, &cached.bytes);
+        }
+
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Err(not_found(self, url));
+        }
+
+        if !response.status().is_success() {
+            return Err(format!("Failed to fetch {}{}: {}", url, attempts_note(attempts), response.status()));
+        }
+
+        let content_type = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or("")
+            .to_ascii_lowercase();
+
+        if content_type.starts_with("text/html") {
+            return Err(format!("Refusing to bundle {}: server returned an HTML page ({})", url, content_type));
+        }
+
+        if response.content_length().is_some_and(|length| length as usize > max_source_bytes) {
+            return Err(format!("Refusing to bundle {}: larger than MAX_SOURCE_BYTES ({} bytes)", url, max_source_bytes));
+        }
+
+        let header = |name| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(|value| value.to_string())
+        };
+        let etag = header(reqwest::header::ETAG);
+        let last_modified = header(reqwest::header::LAST_MODIFIED);
+
+        let bytes = response.bytes().map_err(|err| format!("Failed to read {}: {}", url, err))?;
+
+        if bytes.len() > max_source_bytes {
+            return Err(format!("Refusing to bundle {}: larger than MAX_SOURCE_BYTES ({} bytes)", url, max_source_bytes));
+        }
+
+        let lines = source_lines(url, &bytes)?;
+
+        if let Some(cache) = &self.fetch_cache {
+            cache.put(url, etag.as_deref(), last_modified.as_deref(), &bytes);
+        }
+
+        Ok(lines)
+    }
+}
+
+impl Source for HttpSource {
+    fn root(&self) -> &str {
+        &self.root
+    }
+
+    fn read_lines(&self, url: &str) -> Result<Vec<String>, String> {
+        self.fetch_lines(url, None)
+    }
+
+    fn read_lines_within(&self, url: &str, timeout: Duration) -> Result<Vec<String>, String> {
+        self.fetch_lines(url, Some(timeout))
+    }
+
+    fn check_within_root(&self, path: &str) -> Result<(), String> {
+        let root_url = Url::parse(&format!("{}/", self.root))
+            .map_err(|err| format!("ROOT_DIRECTORY is not a valid URL ({}): {}", err, self.root))?;
+        let url = Url::parse(path).map_err(|err| format!("Invalid source URL ({}): {}", err, path))?;
+
+        let same_origin = url.scheme() == root_url.scheme()
+            && url.host_str() == root_url.host_str()
+            && url.port_or_known_default() == root_url.port_or_known_default();
+
+        if !same_origin || !url.path().starts_with(root_url.path()) {
+            return Err(format!("Refusing to fetch {}: it resolves outside ROOT_DIRECTORY ({})", path, self.root));
+        }
+
+        Ok(())
+    }
+
+    fn list_directory(&self, directory_url: &str) -> Option<Vec<String>> {
+        let (listing_url, entry_re) = match HttpSource::github_contents_url(directory_url) {
+            Some(api_url) => (api_url, Regex::new(r#""name"\s*:\s*"([^"]+)""#).unwrap()),
+            None => (format!("{}/", directory_url.trim_end_matches('/')), Regex::new(r#"href="([^"?#:/][^"?#:]*)""#).unwrap()),
+        };
+
+        if let Err(err) = self.check_allowed(&listing_url) {
+            eprintln!("{}", err);
+            return None;
+        }
+
+        let cached = self.listing_cache.as_ref().and_then(|cache| cache.get(&listing_url));
+
+        if let Some(cached) = cached.as_ref().filter(|cached| cached.fresh) {
+            return Some(cached.entries.clone());
+        }
+
+        let mut request = self.request(reqwest::Method::GET, &listing_url);
+
+        if let Some(etag) = cached.as_ref().and_then(|cached| cached.etag.as_deref()) {
+            request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+        }
+
+        let response = send_with_retries(request, self.retries, None).0.ok()?;
+
+        if let (reqwest::StatusCode::NOT_MODIFIED, Some(cached), Some(cache)) = (response.status(), &cached, &self.listing_cache) {
+            cache.put(&listing_url, cached.etag.as_deref(), &cached.entries);
+            return Some(cached.entries.clone());
+        }
+
+        if !response.status().is_success() {
+            return None;
+        }
+
+        let etag = response
+            .headers()
+            .get(reqwest::header::ETAG)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.to_string());
+        let body = response.text().ok()?;
+
+        let mut entries: Vec<String> = entry_re
+            .captures_iter(&body)
+            .map(|caps| caps[1].trim_end_matches('/').to_string())
+            .filter(|entry| !entry.is_empty() && entry != "..")
+            .collect();
+        entries.sort();
+        entries.dedup();
+
+        if let Some(cache) = &self.listing_cache {
+            cache.put(&listing_url, etag.as_deref(), &entries);
+        }
+
+        Some(entries)
+    }
+
+    fn revision(&self) -> Option<String> {
+        let path = self.root.strip_prefix("https://raw.githubusercontent.com/")?;
+        path.split('/').nth(2).map(|reference| reference.to_string())
+    }
+
+    fn fingerprint(&self, url: &str) -> Option<String> {
+        self.check_allowed(url).ok()?;
+
+        let response = self.request(reqwest::Method::HEAD, url).send().ok()?;
+
+        if !response.status().is_success() {
+            return None;
+        }
+
+        let headers = response.headers();
+        headers
+            .get(reqwest::header::ETAG)
+            .or_else(|| headers.get(reqwest::header::LAST_MODIFIED))
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.to_string())
+    }
+}
+
+pub struct FsSource {
+    root: String,
+    canonical_root: PathBuf,
+}
+
+impl FsSource {
+    fn resolve(path: &Path) -> PathBuf {
+        let mut resolved = if path.is_relative() { env::current_dir().unwrap_or_default() } else { PathBuf::new() };
+
+        for component in path.components() {
+            match component {
+                Component::ParentDir => {
+                    resolved.pop();
+                }
+                Component::CurDir => {}
+                _ => {
+                    resolved.push(component);
+
+                    if let Ok(canonical) = fs::canonicalize(&resolved) {
+                        resolved = canonical;
+                    }
+                }
+            }
+        }
+
+        resolved
+    }
+}
+
+impl Source for FsSource {
+    fn root(&self) -> &str {
+        &self.root
+    }
+
+    fn read_lines(&self, path: &str) -> Result<Vec<String>, String> {
+        self.check_within_root(path)?;
+
+        let max_source_bytes = max_source_bytes();
+
+        let bytes = match fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Err(not_found(self, path)),
+            Err(err) => return Err(format!("Failed to read {}: {}", path, err)),
+        };
+
+        if bytes.len() > max_source_bytes {
+            return Err(format!("Refusing to bundle {}: larger than MAX_SOURCE_BYTES ({} bytes)", path, max_source_bytes));
+        }
+
+        source_lines(path, &bytes)
+    }
+
+    fn check_within_root(&self, path: &str) -> Result<(), String> {
+        if !FsSource::resolve(Path::new(path)).starts_with(&self.canonical_root) {
+            return Err(format!("Refusing to read {}: it resolves outside ROOT_DIRECTORY ({})", path, self.root));
+        }
+
+        Ok(())
+    }
+
+    fn list_directory(&self, path: &str) -> Option<Vec<String>> {
+        let mut entries: Vec<String> = fs::read_dir(path)
+            .ok()?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .filter(|name| !name.starts_with('.'))
+            .collect();
+        entries.sort();
+
+        Some(entries)
+    }
+
+    fn revision(&self) -> Option<String> {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(&self.canonical_root)
+            .args(["rev-parse", "--short", "HEAD"])
+            .output()
+            .ok()?;
+
+        if !output.status.success() {
+            return None;
+        }
+
+        let mut revision = String::from_utf8_lossy(&output.stdout).trim().to_string();
+
+        let status = Command::new("git")
+            .arg("-C")
+            .arg(&self.canonical_root)
+            .args(["status", "--porcelain", "--", "."])
+            .output()
+            .ok()?;
+
+        if !status.stdout.is_empty() {
+            revision.push_str("-dirty");
+        }
+
+        Some(revision)
+    }
+
+    fn fingerprint(&self, path: &str) -> Option<String> {
+        self.check_within_root(path).ok()?;
+
+        let metadata = fs::metadata(path).ok()?;
+        let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+
+        Some(format!("{}.{}-{}", modified.as_secs(), modified.subsec_nanos(), metadata.len()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+
+    fn fixture(name: &str) -> PathBuf {
+        let directory = env::temp_dir().join(format!("ti84-source-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+
+        for (path, content) in [("project/games/snake/download.py", "print(1)\n"), ("project/common/helpers.py", "X = 1\n"), ("outside/secret.py", "TOKEN = 1\n")] {
+            let path = directory.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+
+        directory
+    }
+
+    fn fs_source(directory: &Path) -> FsSource {
+        let root = directory.join("project");
+
+        FsSource { root: root.to_string_lossy().to_string(), canonical_root: fs::canonicalize(&root).unwrap() }
+    }
+
+    #[test]
+    fn fs_source_reads_inside_the_root() {
+        let directory = fixture("inside");
+        let source = fs_source(&directory);
+
+        assert_eq!(source.read_lines(&format!("{}/games/snake/download.py", source.root)).unwrap(), vec!["print(1)"]);
+        assert_eq!(source.read_lines(&format!("{}/games/../common/helpers.py", source.root)).unwrap(), vec!["X = 1"]);
+        assert!(source.check_within_root(&format!("{}/games/not_written_yet.py", source.root)).is_ok());
+    }
+
+    #[test]
+    fn fs_source_refuses_parent_directory_escapes() {
+        let directory = fixture("parent");
+        let source = fs_source(&directory);
+
+        for path in ["../outside/secret.py", "games/../../outside/secret.py", "missing/deeper/../../../outside/secret.py"] {
+            let path = format!("{}/{}", source.root, path);
+
+            assert!(source.check_within_root(&path).is_err(), "{} should be outside the root", path);
+            assert!(source.read_lines(&path).unwrap_err().contains("resolves outside ROOT_DIRECTORY"));
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn fs_source_refuses_symlink_escapes() {
+        let directory = fixture("symlink");
+        let source = fs_source(&directory);
+
+        std::os::unix::fs::symlink(directory.join("outside/secret.py"), directory.join("project/games/helpers.py")).unwrap();
+        std::os::unix::fs::symlink(directory.join("outside"), directory.join("project/vendor")).unwrap();
+        std::os::unix::fs::symlink(directory.join("project/common"), directory.join("project/shared")).unwrap();
+
+        for path in ["games/helpers.py", "vendor/secret.py", "vendor/not_there.py"] {
+            let path = format!("{}/{}", source.root, path);
+
+            assert!(source.read_lines(&path).unwrap_err().contains("resolves outside ROOT_DIRECTORY"), "{} should be refused", path);
+        }
+
+        assert_eq!(source.read_lines(&format!("{}/shared/helpers.py", source.root)).unwrap(), vec!["X = 1"]);
+    }
+
+    fn http_source(root: &str, allowlist: Option<Vec<String>>, credentials: Option<Credentials>) -> HttpSource {
+        HttpSource {
+            root: root.to_string(),
+            client: http_client(allowlist.clone()),
+            allowlist,
+            listing_cache: None,
+            fetch_cache: None,
+            credentials,
+            retries: 0,
+        }
+    }
+
+    fn serve(responses: Vec<String>) -> (String, mpsc::Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = format!("127.0.0.1:{}", listener.local_addr().unwrap().port());
+        let (sender, receiver) = mpsc::channel();
+
+        thread::spawn(move || {
+            for response in responses {
+                let Ok((mut stream, _)) = listener.accept() else {
+                    return;
+                };
+
+                let mut request = Vec::new();
+                let mut buffer = [0; 1024];
+                while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+                    match stream.read(&mut buffer) {
+                        Ok(0) | Err(_) => break,
+                        Ok(read) => request.extend_from_slice(&buffer[..read]),
+                    }
+                }
+
+                let _ = sender.send(String::from_utf8_lossy(&request).to_ascii_lowercase());
+                let _ = stream.write_all(response.as_bytes());
+            }
+        });
+
+        (address, receiver)
+    }
+
+    fn redirect_to(location: &str) -> String {
+        format!("HTTP/1.1 302 Found\r\nLocation: {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", location)
+    }
+
+    fn python_body(body: &str) -> String {
+        format!("HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", body.len(), body)
+    }
+
+    #[test]
+    fn allowlist_matches_hosts_ports_and_url_prefixes() {
+        let allowlist = vec![String::from("example.com"), String::from("mirror.test:8080"), String::from("https://raw.githubusercontent.com/class/scripts")];
+        let allowed = |url: &str| is_allowed(&Url::parse(url).unwrap(), &allowlist);
+
+        assert!(allowed("https://EXAMPLE.com/a.py"));
+        assert!(allowed("http://mirror.test:8080/a.py"));
+        assert!(!allowed("http://mirror.test:9090/a.py"));
+        assert!(allowed("https://raw.githubusercontent.com/class/scripts/main/a.py"));
+        assert!(!allowed("https://raw.githubusercontent.com/class/scripts-fork/main/a.py"));
+        assert!(!allowed("https://example.com.evil.test/a.py"));
+    }
+
+    #[test]
+    fn redirects_outside_the_allowlist_are_not_followed() {
+        let (elsewhere, elsewhere_requests) = serve(vec![python_body("STOLEN = 1\n")]);
+        let (address, _) = serve(vec![redirect_to(&format!("http://{}/payload.py", elsewhere))]);
+        let source = http_source(&format!("http://{}/project", address), Some(vec![address.clone()]), None);
+
+        let err = source.read_lines(&format!("http://{}/project/games/snake/download.py", address)).unwrap_err();
+
+        assert!(err.contains("is not covered by FETCH_ALLOWLIST"), "{}", err);
+        assert!(elsewhere_requests.recv_timeout(Duration::from_millis(200)).is_err());
+    }
+
+    #[test]
+    fn fetches_outside_the_allowlist_are_refused() {
+        let source = http_source("http://127.0.0.1:9/project", Some(vec![String::from("example.com")]), None);
+
+        let err = source.read_lines("http://127.0.0.1:9/project/games/snake/download.py").unwrap_err();
+
+        assert!(err.starts_with("Refusing to fetch"), "{}", err);
+    }
+
+    #[test]
+    fn credentials_are_only_sent_to_the_root_host() {
+        let source = http_source("https://scripts.example.com/class", None, Some(Credentials::Bearer(String::from("sekret"))));
+        let authorization = |url: &str| {
+            let request = source.request(reqwest::Method::GET, url).build().unwrap();
+            request.headers().get(reqwest::header::AUTHORIZATION).map(|value| value.to_str().unwrap().to_string())
+        };
+
+        assert_eq!(authorization("https://scripts.example.com/class/a.py").as_deref(), Some("Bearer sekret"));
+        assert_eq!(authorization("https://cdn.example.com/class/a.py"), None);
+        assert_eq!(authorization("https://api.github.com/repos/class/scripts/contents/"), None);
+
+        let github = http_source("https://raw.githubusercontent.com/class/scripts/main", None, Some(Credentials::Bearer(String::from("sekret"))));
+        let request = github.request(reqwest::Method::GET, "https://api.github.com/repos/class/scripts/contents/games").build().unwrap();
+        assert!(request.headers().contains_key(reqwest::header::AUTHORIZATION));
+    }
+
+    #[test]
+    fn credentials_are_dropped_when_redirected_to_another_host() {
+        let (elsewhere, elsewhere_requests) = serve(vec![python_body("X = 1\n")]);
+        let elsewhere = elsewhere.replace("127.0.0.1", "localhost");
+        let (address, requests) = serve(vec![redirect_to(&format!("http://{}/a.py", elsewhere))]);
+        let source = http_source(&format!("http://{}/project", address), None, Some(Credentials::Bearer(String::from("sekret"))));
+
+        assert_eq!(source.read_lines(&format!("http://{}/project/a.py", address)).unwrap(), vec!["X = 1"]);
+        assert!(requests.recv().unwrap().contains("authorization: bearer sekret"));
+        assert!(!elsewhere_requests.recv().unwrap().contains("authorization"));
+    }
+
+    #[test]
+    fn http_root_check_normalizes_parent_segments() {
+        let source = http_source("https://scripts.example.com/class", None, None);
+
+        assert!(source.check_within_root("https://scripts.example.com/class/games/a.py").is_ok());
+        assert!(source.check_within_root("https://scripts.example.com/class/../secrets/a.py").is_err());
+        assert!(source.check_within_root("https://scripts.example.com/classic/a.py").is_err());
+        assert!(source.check_within_root("https://other.example.com/class/a.py").is_err());
+    }
+}
@@ -0,0 +1,274 @@
+use rustpython_parser::ast::{self, Ranged};
+use rustpython_parser::{StringKind, Tok};
+use crate::python::{self, line_index};
+use crate::rules::Violation;
+
+pub struct Profile {
+    pub os_version: &'static str,
+    f_strings: bool,
+    assignment_expressions: bool,
+    pub time_functions: &'static [&'static str],
+    pub columns: usize,
+    pub rows: usize,
+}
+
+const TIME_FUNCTIONS: &[&str] = &["sleep", "monotonic"];
+
+const PROFILES: &[Profile] = &[
+    Profile { os_version: "5.5", f_strings: false, assignment_expressions: false, time_functions: TIME_FUNCTIONS, columns: 32, rows: 11 },
+    Profile { os_version: "5.6", f_strings: false, assignment_expressions: false, time_functions: TIME_FUNCTIONS, columns: 32, rows: 11 },
+    Profile { os_version: "5.7", f_strings: true, assignment_expressions: false, time_functions: TIME_FUNCTIONS, columns: 32, rows: 11 },
+    Profile { os_version: "5.8", f_strings: true, assignment_expressions: false, time_functions: TIME_FUNCTIONS, columns: 32, rows: 11 },
+];
+
+pub fn profile(os_version: &str) -> Option<&'static Profile> {
+    PROFILES.iter().find(|profile| profile.os_version == os_version)
+}
+
+pub fn os_versions() -> Vec<&'static str> {
+    PROFILES.iter().map(|profile| profile.os_version).collect()
+}
+
+pub struct SubsetReport {
+    pub violations: Vec<Violation>,
+    pub warnings: Vec<String>,
+}
+
+const MAX_SMALL_INT: i64 = (1 << 30) - 1;
+const FLOAT_DIGITS: usize = 7;
+
+struct Checker<'a> {
+    lines: &'a [String],
+    line_starts: &'a [usize],
+    tokens: &'a [(Tok, usize, usize)],
+    report: SubsetReport,
+}
+
+impl Checker<'_> {
+    fn line_number(&self, offset: usize) -> usize {
+        line_index(self.line_starts, offset) + 1
+    }
+
+    fn flag(&mut self, rule_id: &'static str, offset: usize, message: &'static str) {
+        let line_number = self.line_number(offset);
+
+        if self.report.violations.iter().any(|violation| violation.rule_id == rule_id && violation.line_number == line_number) {
+            return;
+        }
+
+        self.report.violations.push(Violation {
+            rule_id,
+            line_number,
+            line: self.lines.get(line_number - 1).map(|line| line.trim().to_string()).unwrap_or_default(),
+            message,
+        });
+    }
+
+    fn check_recursion(&mut self, name: &str, start: usize, end: usize) {
+        let tokens: Vec<&(Tok, usize, usize)> = self.tokens.iter().filter(|(_, offset, _)| (start..end).contains(offset)).collect();
+
+        let recursive_call = tokens.windows(3).find_map(|window| match window {
+            [(previous, _, _), (Tok::Name { name: called }, offset, _), (Tok::Lpar, _, _)]
+                if called == name && !matches!(previous, Tok::Def | Tok::Dot) =>
+            {
+                Some(*offset)
+            }
+            _ => None,
+        });
+
+        if let Some(offset) = recursive_call {
+            self.report.warnings.push(format!(
+                "{}: {}() calls itself; the calculator caps recursion depth, so deep recursion will fail there",
+                self.line_number(offset),
+                name
+            ));
+        }
+    }
+
+    fn warn(&mut self, offset: usize, message: String) {
+        let line_number = self.line_number(offset);
+        self.report.warnings.push(format!("{}: {}", line_number, message));
+    }
+
+    fn operand_is_float(&self, index: usize, forward: bool) -> bool {
+        let (opening, closing) = if forward {
+            ([Tok::Lpar, Tok::Lsqb, Tok::Lbrace], [Tok::Rpar, Tok::Rsqb, Tok::Rbrace])
+        } else {
+            ([Tok::Rpar, Tok::Rsqb, Tok::Rbrace], [Tok::Lpar, Tok::Lsqb, Tok::Lbrace])
+        };
+        let operand: Box<dyn Iterator<Item = &(Tok, usize, usize)>> = if forward {
+            Box::new(self.tokens[index + 1..].iter())
+        } else {
+            Box::new(self.tokens[..index].iter().rev())
+        };
+
+        let mut depth = 0;
+
+        for (token, _, _) in operand {
+            if opening.contains(token) {
+                depth += 1;
+            } else if closing.contains(token) {
+                if depth == 0 {
+                    return false;
+                }
+                depth -= 1;
+            } else if matches!(token, Tok::Float { .. } | Tok::Slash) {
+                return true;
+            } else if depth == 0
+                && matches!(
+                    token,
+                    Tok::Newline | Tok::Colon | Tok::Comma | Tok::Equal | Tok::And | Tok::Or | Tok::Not | Tok::If | Tok::Elif
+                        | Tok::While | Tok::Return | Tok::Else | Tok::Semi | Tok::EqEqual | Tok::NotEqual | Tok::Less
+                        | Tok::LessEqual | Tok::Greater | Tok::GreaterEqual
+                )
+            {
+                return false;
+            }
+        }
+
+        false
+    }
+
+    fn check_numbers(&mut self, source: &str) {
+        let mut warnings = Vec::new();
+
+        for (index, (token, offset, _)) in self.tokens.iter().enumerate() {
+            match token {
+                Tok::Int { value } if i64::try_from(value).map_or(true, |value| value.abs() > MAX_SMALL_INT) => {
+                    warnings.push((*offset, format!("integer {} is outside MicroPython's 31-bit small integers; big-integer arithmetic is slow on the calculator", value)));
+                }
+                Tok::Float { .. } => {
+                    let literal: String = source[*offset..]
+                        .chars()
+                        .take_while(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '+' | '-'))
+                        .collect();
+                    let mantissa: String = literal.split(['e', 'E']).next().unwrap_or("").chars().filter(|c| c.is_ascii_digit()).collect();
+                    let significant = mantissa.trim_start_matches('0').trim_end_matches('0').len();
+
+                    if significant > FLOAT_DIGITS {
+                        warnings.push((*offset, format!("{} has more digits than the calculator's single-precision floats keep (about {})", literal, FLOAT_DIGITS)));
+                    }
+                }
+                Tok::EqEqual | Tok::NotEqual if self.operand_is_float(index, false) || self.operand_is_float(index, true) => {
+                    warnings.push((*offset, String::from("exact == or != on a float result; rounding differs on the calculator, so compare with abs(a - b) < tolerance")));
+                }
+                _ => {}
+            }
+        }
+
+        for (offset, message) in warnings {
+            self.warn(offset, message);
+        }
+    }
+
+    fn body(&mut self, body: &[ast::Stmt]) {
+        for statement in body {
+            self.stmt(statement);
+        }
+    }
+
+    fn stmt(&mut self, statement: &ast::Stmt) {
+        let start = usize::from(statement.start());
+
+        match statement {
+            ast::Stmt::FunctionDef(def) => {
+                self.check_recursion(&def.name, start, usize::from(def.end()));
+                self.body(&def.body);
+            }
+            ast::Stmt::AsyncFunctionDef(def) => self.body(&def.body),
+            ast::Stmt::ClassDef(def) => self.body(&def.body),
+            ast::Stmt::For(block) => {
+                self.body(&block.body);
+                self.body(&block.orelse);
+            }
+            ast::Stmt::While(block) => {
+                self.body(&block.body);
+                self.body(&block.orelse);
+            }
+            ast::Stmt::If(block) => {
+                self.body(&block.body);
+                self.body(&block.orelse);
+            }
+            ast::Stmt::With(block) => self.body(&block.body),
+            ast::Stmt::Try(block) => {
+                self.body(&block.body);
+                for ast::ExceptHandler::ExceptHandler(handler) in &block.handlers {
+                    self.body(&handler.body);
+                }
+                self.body(&block.orelse);
+                self.body(&block.finalbody);
+            }
+            ast::Stmt::Match(block) => {
+                self.flag("PY003", start, "match statements are not supported by the calculator's Python");
+                for case in &block.cases {
+                    self.body(&case.body);
+                }
+            }
+            ast::Stmt::Import(import) if import.names.iter().any(|alias| alias.name.split('.').next() == Some("typing")) => {
+                self.flag("PY002", start, "the typing module is not available on the calculator");
+            }
+            ast::Stmt::ImportFrom(import) if import.module.as_deref().and_then(|module| module.split('.').next()) == Some("typing") => {
+                self.flag("PY002", start, "the typing module is not available on the calculator");
+            }
+            _ => {}
+        }
+    }
+}
+
+pub fn check_subset(lines: &[String], profile: &Profile) -> SubsetReport {
+    let parsed = python::parse_source(lines);
+
+    let mut checker = Checker {
+        lines,
+        line_starts: &parsed.line_starts,
+        tokens: &parsed.tokens,
+        report: SubsetReport { violations: Vec::new(), warnings: Vec::new() },
+    };
+
+    let flagged_tokens: Vec<(&'static str, usize, &'static str)> = checker
+        .tokens
+        .iter()
+        .filter_map(|(token, offset, _)| match token {
+            Tok::String { kind: StringKind::FString | StringKind::RawFString, .. } if !profile.f_strings => {
+                Some(("PY001", *offset, "f-strings are not supported on this OS version; use str() and + or format()"))
+            }
+            Tok::ColonEqual if !profile.assignment_expressions => {
+                Some(("PY005", *offset, "assignment expressions (:=) are not supported by the calculator's Python"))
+            }
+            Tok::Async | Tok::Await => Some(("PY004", *offset, "async and await are not supported by the calculator's Python")),
+            _ => None,
+        })
+        .collect();
+
+    for (rule_id, offset, message) in flagged_tokens {
+        checker.flag(rule_id, offset, message);
+    }
+
+    checker.check_numbers(&parsed.source);
+
+    if let Ok(suite) = &parsed.suite {
+        checker.body(suite);
+    }
+
+    checker.report.violations.sort_by_key(|violation| violation.line_number);
+    checker.report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn big_integer_warnings(source: &str) -> usize {
+        let lines: Vec<String> = source.lines().map(|line| line.to_string()).collect();
+        let report = check_subset(&lines, profile("5.6").unwrap());
+
+        report.warnings.iter().filter(|warning| warning.contains("small integers")).count()
+    }
+
+    #[test]
+    fn small_integers_stop_at_thirty_bits() {
+        assert_eq!(big_integer_warnings("x = 1073741823\ny = -1073741823"), 0);
+        assert_eq!(big_integer_warnings("x = 1073741824"), 1);
+        assert_eq!(big_integer_warnings("x = 2147483647"), 1);
+    }
+}
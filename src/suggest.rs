@@ -0,0 +1,59 @@
+use crate::source::Source;
+
+fn edit_distance(first: &str, second: &str) -> usize {
+    let second_chars: Vec<char> = second.chars().collect();
+    let mut previous: Vec<usize> = (0..=second_chars.len()).collect();
+
+    for (i, first_char) in first.chars().enumerate() {
+        let mut current = vec![i + 1];
+
+        for (j, second_char) in second_chars.iter().enumerate() {
+            let substitution = previous[j] + usize::from(first_char != *second_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+
+        previous = current;
+    }
+
+    previous[second_chars.len()]
+}
+
+pub fn closest_match<'a>(missing: &str, candidates: &'a [String]) -> Option<&'a String> {
+    candidates
+        .iter()
+        .map(|candidate| (edit_distance(missing, candidate), candidate))
+        .filter(|(distance, candidate)| *distance <= 2.max(missing.len() / 3) || candidate.starts_with(missing))
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+pub fn diagnose_missing(url: &str, source: &dyn Source) -> Vec<String> {
+    let root = source.root();
+    let relative = url.strip_prefix(root).unwrap_or(url).trim_start_matches('/');
+
+    let mut diagnostics = vec![format!("Not found: {}", relative)];
+
+    let mut directory_url = root.to_string();
+    let mut directory_name = String::from("ROOT_DIRECTORY");
+
+    for segment in relative.split('/') {
+        let Some(entries) = source.list_directory(&directory_url) else {
+            break;
+        };
+
+        if !entries.iter().any(|entry| entry == segment) {
+            diagnostics.push(format!("  {} contains: {}", directory_name, entries.join(", ")));
+
+            if let Some(suggestion) = closest_match(segment, &entries) {
+                diagnostics.push(format!("  did you mean `{}`?", suggestion));
+            }
+
+            break;
+        }
+
+        directory_url = format!("{}/{}", directory_url, segment);
+        directory_name = format!("{}/", directory_url.strip_prefix(root).unwrap_or(&directory_url).trim_start_matches('/'));
+    }
+
+    diagnostics
+}
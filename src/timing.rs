@@ -0,0 +1,162 @@
+use rustpython_parser::ast::{self, Ranged};
+use rustpython_parser::Tok;
+use crate::python::{self, child_bodies};
+use crate::subset::Profile;
+
+const TIME_MAPPINGS: &[(&str, &str)] = &[("time", "monotonic"), ("perf_counter", "monotonic"), ("process_time", "monotonic")];
+
+pub struct TimeMapping {
+    pub lines: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+fn mapped_name(function: &str, profile: &Profile) -> Option<&'static str> {
+    TIME_MAPPINGS
+        .iter()
+        .find(|(desktop, _)| *desktop == function)
+        .map(|(_, calculator)| *calculator)
+        .filter(|calculator| profile.time_functions.contains(calculator))
+}
+
+fn collect_time_imports(body: &[ast::Stmt], aliases: &mut Vec<String>, imported: &mut Vec<(usize, usize, String, Option<String>)>) {
+    for statement in body {
+        match statement {
+            ast::Stmt::Import(import) => {
+                for alias in import.names.iter().filter(|alias| alias.name.as_str() == "time") {
+                    aliases.push(alias.asname.as_ref().unwrap_or(&alias.name).to_string());
+                }
+            }
+            ast::Stmt::ImportFrom(import) if import.module.as_deref() == Some("time") => {
+                for alias in &import.names {
+                    imported.push((
+                        usize::from(alias.start()),
+                        usize::from(alias.end()),
+                        alias.name.to_string(),
+                        alias.asname.as_ref().map(|asname| asname.to_string()),
+                    ));
+                }
+            }
+            _ => {
+                for child in child_bodies(statement) {
+                    collect_time_imports(child, aliases, imported);
+                }
+            }
+        }
+    }
+}
+
+pub fn map_time_api(lines: Vec<String>, profile: &Profile) -> TimeMapping {
+    let parsed = python::parse_source(&lines);
+
+    let Ok(suite) = &parsed.suite else {
+        return TimeMapping { lines, warnings: Vec::new() };
+    };
+
+    let mut aliases = Vec::new();
+    let mut imported = Vec::new();
+    collect_time_imports(suite, &mut aliases, &mut imported);
+
+    let mut replacements: Vec<(usize, usize, String)> = Vec::new();
+    let mut warnings = Vec::new();
+    let mut note = |offset: usize, message: String| warnings.push(format!("{}: {}", parsed.line_index(offset) + 1, message));
+
+    for (start, end, function, asname) in imported {
+        if profile.time_functions.contains(&function.as_str()) {
+            continue;
+        }
+
+        match mapped_name(&function, profile) {
+            Some(calculator) => {
+                let bound = asname.as_deref().unwrap_or(&function);
+                replacements.push((start, end, format!("{} as {}", calculator, bound)));
+                note(start, format!("time.{} is imported as time.{}, which counts from power-on rather than the epoch", function, calculator));
+            }
+            None => note(start, format!("time.{} is not available on the calculator (OS {})", function, profile.os_version)),
+        }
+    }
+
+    if !aliases.is_empty() {
+        let tokens = &parsed.tokens;
+
+        for (index, window) in tokens.windows(3).enumerate() {
+            let [(Tok::Name { name: module }, _, _), (Tok::Dot, _, _), (Tok::Name { name: function }, start, end)] = window else {
+                continue;
+            };
+
+            if index > 0 && matches!(tokens[index - 1].0, Tok::Dot) {
+                continue;
+            }
+
+            if !aliases.contains(module) || profile.time_functions.contains(&function.as_str()) {
+                continue;
+            }
+
+            match mapped_name(function, profile) {
+                Some(calculator) => {
+                    replacements.push((*start, *end, calculator.to_string()));
+                    note(*start, format!("{}.{} is mapped to {}.{}, which counts from power-on rather than the epoch", module, function, module, calculator));
+                }
+                None => note(*start, format!("{}.{} is not available on the calculator (OS {})", module, function, profile.os_version)),
+            }
+        }
+    }
+
+    if replacements.is_empty() {
+        return TimeMapping { lines, warnings };
+    }
+
+    replacements.sort_by_key(|(start, _, _)| *start);
+
+    let mut mapped = parsed.source.clone();
+    for (start, end, replacement) in replacements.into_iter().rev() {
+        mapped.replace_range(start..end, &replacement);
+    }
+
+    TimeMapping {
+        lines: mapped.split('\n').map(|line| line.to_string()).collect(),
+        warnings,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mapped(source: &str) -> (String, Vec<String>) {
+        let lines: Vec<String> = source.lines().map(|line| line.to_string()).collect();
+        let mapping = map_time_api(lines, crate::subset::profile("5.6").unwrap());
+
+        (mapping.lines.join("\n"), mapping.warnings)
+    }
+
+    #[test]
+    fn module_calls_are_mapped_to_monotonic() {
+        let (source, warnings) = mapped("import time\nstart = time.time()\nnow = time.perf_counter()");
+
+        assert_eq!(source, "import time\nstart = time.monotonic()\nnow = time.monotonic()");
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].starts_with("2: time.time is mapped to time.monotonic"));
+    }
+
+    #[test]
+    fn aliased_imports_are_mapped() {
+        let (source, _) = mapped("import time as t\nfrom time import perf_counter as clock\nt.time()\nclock()");
+
+        assert_eq!(source, "import time as t\nfrom time import monotonic as clock\nt.monotonic()\nclock()");
+    }
+
+    #[test]
+    fn attributes_named_time_are_left_alone() {
+        let source = "import time\nself.time.time()\ncfg.time.perf_counter\ntime.sleep(1)";
+
+        assert_eq!(mapped(source), (source.to_string(), Vec::new()));
+    }
+
+    #[test]
+    fn imports_inside_nested_blocks_are_found() {
+        let (source, warnings) = mapped("class Timer:\n    from time import time\ntry:\n    pass\nexcept ImportError:\n    from time import localtime");
+
+        assert!(source.contains("from time import monotonic as time"));
+        assert!(warnings.iter().any(|warning| warning.starts_with("6: time.localtime is not available")));
+    }
+}
@@ -0,0 +1,28 @@
+use std::env;
+use std::path::Path;
+
+pub struct MissingTool {
+    pub name: String,
+    pub skipped: String,
+    pub hint: String,
+}
+
+pub fn on_path(program: &str) -> bool {
+    let program = Path::new(program);
+
+    if program.components().count() > 1 {
+        return program.is_file();
+    }
+
+    let suffixes: &[&str] = if cfg!(target_os = "windows") { &[".exe", ".cmd", ".bat", ""] } else { &[""] };
+
+    env::var_os("PATH").is_some_and(|paths| {
+        env::split_paths(&paths).any(|directory| {
+            suffixes.iter().any(|suffix| {
+                let mut file_name = program.as_os_str().to_os_string();
+                file_name.push(suffix);
+                directory.join(file_name).is_file()
+            })
+        })
+    })
+}
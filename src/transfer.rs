@@ -0,0 +1,64 @@
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use crate::tools::MissingTool;
+
+fn install_candidates() -> Vec<PathBuf> {
+    if cfg!(target_os = "macos") {
+        let mut candidates = vec![PathBuf::from("/Applications/TI Connect CE.app")];
+        if let Ok(home) = env::var("HOME") {
+            candidates.push(Path::new(&home).join("Applications/TI Connect CE.app"));
+        }
+        candidates
+    } else if cfg!(target_os = "windows") {
+        ["ProgramFiles(x86)", "ProgramFiles", "LOCALAPPDATA"]
+            .iter()
+            .filter_map(|variable| env::var(variable).ok())
+            .map(|directory| Path::new(&directory).join("TI Education").join("TI Connect CE").join("TI Connect CE.exe"))
+            .collect()
+    } else {
+        Vec::new()
+    }
+}
+
+pub fn ti_connect_path() -> Option<PathBuf> {
+    if let Ok(path) = env::var("TI_CONNECT_PATH") {
+        return Some(PathBuf::from(path)).filter(|path| path.exists());
+    }
+
+    install_candidates().into_iter().find(|path| path.exists())
+}
+
+pub fn missing_tools() -> Vec<MissingTool> {
+    if ti_connect_path().is_some() {
+        return Vec::new();
+    }
+
+    let hint = if cfg!(any(target_os = "macos", target_os = "windows")) {
+        "install it from education.ti.com or set TI_CONNECT_PATH to its program"
+    } else {
+        "TI Connect CE only runs on Windows and macOS; set TI_CONNECT_PATH to another sending program such as tilp"
+    };
+
+    vec![MissingTool {
+        name: String::from("TI Connect CE"),
+        skipped: String::from("the .8xv files will be written but not sent"),
+        hint: hint.to_string(),
+    }]
+}
+
+pub fn open_with_files(program: &Path, files: &[PathBuf]) -> Result<(), String> {
+    let mut command = if program.extension().is_some_and(|extension| extension == "app") {
+        let mut command = Command::new("open");
+        command.arg("-a").arg(program);
+        command
+    } else {
+        Command::new(program)
+    };
+
+    command
+        .args(files)
+        .spawn()
+        .map(|_| ())
+        .map_err(|err| format!("Failed to start {}: {}", program.display(), err))
+}
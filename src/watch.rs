@@ -0,0 +1,52 @@
+use std::collections::HashMap;
+use std::time::Duration;
+use crate::source::Source;
+
+pub const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+pub struct WatchedScript {
+    pub script_name: String,
+    paths: Vec<String>,
+}
+
+pub fn watched_scripts(source: &dyn Source, group: &str, script_names: &[String]) -> Vec<WatchedScript> {
+    let common_helpers = format!("{}/common/helpers.py", source.root());
+
+    script_names
+        .iter()
+        .map(|script_name| {
+            let directory = format!("{}/{}/{}", source.root(), group, script_name);
+
+            let mut paths: Vec<String> = source
+                .list_directory(&directory)
+                .map(|entries| entries.into_iter().filter(|entry| entry.ends_with(".py")).collect())
+                .unwrap_or_else(|| vec![String::from("download.py"), String::from("script.py")])
+                .into_iter()
+                .map(|entry| format!("{}/{}", directory, entry))
+                .collect();
+            paths.push(common_helpers.clone());
+
+            WatchedScript { script_name: script_name.clone(), paths }
+        })
+        .collect()
+}
+
+pub fn fingerprints(source: &dyn Source, scripts: &[WatchedScript]) -> HashMap<String, Option<String>> {
+    let mut fingerprints = HashMap::new();
+
+    for path in scripts.iter().flat_map(|script| &script.paths) {
+        if !fingerprints.contains_key(path) {
+            fingerprints.insert(path.clone(), source.fingerprint(path));
+        }
+    }
+
+    fingerprints
+}
+
+pub fn changed_scripts(scripts: &[WatchedScript], before: &HashMap<String, Option<String>>, after: &HashMap<String, Option<String>>) -> Vec<String> {
+    scripts
+        .iter()
+        .filter(|script| script.paths.iter().any(|path| before.get(path) != after.get(path)))
+        .map(|script| script.script_name.clone())
+        .collect()
+}
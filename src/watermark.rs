@@ -0,0 +1,56 @@
+use std::collections::HashSet;
+use rustpython_parser::Tok;
+use crate::python;
+
+const OBFUSCATION_KEY: &[u8] = b"ti84";
+
+pub fn obfuscate_identifier(identifier: &str) -> String {
+    identifier
+        .bytes()
+        .zip(OBFUSCATION_KEY.iter().cycle())
+        .map(|(byte, key)| format!("{:02x}", byte ^ key))
+        .collect()
+}
+
+fn string_lines(lines: &[String]) -> HashSet<usize> {
+    let parsed = python::parse_source(lines);
+
+    parsed
+        .tokens
+        .iter()
+        .filter(|(token, _, _)| matches!(token, Tok::String { .. }))
+        .flat_map(|(_, start, end)| {
+            let first = parsed.line_index(*start);
+            let last = parsed.line_index(*end);
+            (first..=last).filter(move |_| first != last)
+        })
+        .collect()
+}
+
+pub fn embed_watermark(lines: Vec<String>, identifier: &str) -> Vec<String> {
+    let bits: Vec<bool> = identifier
+        .bytes()
+        .flat_map(|byte| (0..8).rev().map(move |shift| (byte >> shift) & 1 == 1))
+        .collect();
+
+    let mut output_lines = vec![format!("_wm = \"{}\"", obfuscate_identifier(identifier))];
+    let mut bit_index = 0;
+    let string_lines = string_lines(&lines);
+
+    for (index, line) in lines.into_iter().enumerate() {
+        if bits.is_empty() || line.trim().is_empty() || line.trim_end().ends_with('\\') || string_lines.contains(&index) {
+            output_lines.push(line);
+            continue;
+        }
+
+        let mut marked_line = line.trim_end().to_string();
+        if bits[bit_index % bits.len()] {
+            marked_line.push(' ');
+        }
+        bit_index += 1;
+
+        output_lines.push(marked_line);
+    }
+
+    output_lines
+}
@@ -0,0 +1,50 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+use std::process::Output;
+use common::{compiler, fixture};
+
+const FILES: &[(&str, &str)] = &[
+    ("common/helpers.py", "X = 1\n"),
+    ("games/a/download.py", "print(1)\n"),
+    ("games/b/download.py", "print(\n"),
+    ("games/c/download.py", "print(3)\n"),
+];
+
+fn run_batch(root: &Path, extra_args: &[&str]) -> Output {
+    let output_directory = root.join("out");
+
+    compiler(root)
+        .args(["bundle", "-g", "games", "-s", "a,b,c", "--unpacked", "--output", output_directory.to_str().unwrap()])
+        .args(extra_args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn a_failing_script_does_not_stop_the_rest_of_the_batch() {
+    let root = fixture("batches-recover", FILES);
+    let output = run_batch(&root, &[]);
+    let stderr = String::from_utf8(output.stderr).unwrap();
+
+    assert!(!output.status.success());
+    assert!(stderr.contains("b.py"), "{}", stderr);
+    assert!(root.join("out/a.py").exists() && root.join("out/c.py").exists(), "{}", stderr);
+
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn fail_fast_stops_at_the_first_failing_script() {
+    let root = fixture("batches-fail-fast", FILES);
+    let output = run_batch(&root, &["--fail-fast", "--jobs", "1"]);
+    let stderr = String::from_utf8(output.stderr).unwrap();
+
+    assert!(!output.status.success());
+    assert_eq!(stderr.matches("error:").count(), 1, "{}", stderr);
+    assert!(stderr.contains("error: b.py:"), "{}", stderr);
+    assert!(!root.join("out").exists(), "nothing should be written after a fail-fast error");
+
+    fs::remove_dir_all(root).unwrap();
+}
@@ -0,0 +1,65 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+use std::process::Output;
+use std::thread;
+use common::{compiler, fixture};
+
+const FILES: &[(&str, &str)] = &[
+    ("common/helpers.py", "def clamp(value, low, high):\n    return max(low, min(value, high))\n"),
+    ("games/physics.py", "from common.helpers import clamp\n\ndef step(speed):\n    return clamp(speed + 1, 0, 9)\n"),
+    ("games/pong/download.py", "from games.physics import step\nprint(step(1))\n"),
+    ("games/snake/download.py", "from common.helpers import clamp\nprint(clamp(12, 0, 9))\n"),
+];
+
+fn run_bundle(root: &Path, cache: &Path) -> Output {
+    compiler(root).args(["bundle", "-g", "games", "-s", "pong,snake", "-q"]).env("AST_CACHE_DIR", cache).output().unwrap()
+}
+
+#[test]
+fn concurrent_runs_share_the_ast_cache() {
+    let root = fixture("cache-concurrent", FILES);
+    let cache = root.join("ast-cache");
+
+    let outputs: Vec<Output> = thread::scope(|scope| {
+        let runs: Vec<_> = (0..8).map(|_| scope.spawn(|| run_bundle(&root, &cache))).collect();
+        runs.into_iter().map(|run| run.join().unwrap()).collect()
+    });
+
+    for output in &outputs {
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+        assert_eq!(output.stdout, outputs[0].stdout);
+    }
+
+    let leftovers: Vec<String> = fs::read_dir(&cache)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+        .filter(|name| name.ends_with(".tmp"))
+        .collect();
+    assert!(leftovers.is_empty(), "temporary files were left behind: {:?}", leftovers);
+
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn torn_cache_entries_are_treated_as_misses() {
+    let root = fixture("cache-torn", FILES);
+    let cache = root.join("ast-cache");
+    let expected = run_bundle(&root, &cache);
+    assert!(expected.status.success(), "{}", String::from_utf8_lossy(&expected.stderr));
+
+    for entry in fs::read_dir(&cache).unwrap() {
+        let path = entry.unwrap().path();
+        let content = fs::read(&path).unwrap();
+        let last_line = content.iter().rposition(|byte| *byte == b'\n').unwrap();
+        fs::write(&path, &content[..last_line]).unwrap();
+    }
+
+    let output = run_bundle(&root, &cache);
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(output.stdout, expected.stdout);
+
+    fs::remove_dir_all(root).unwrap();
+}
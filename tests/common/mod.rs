@@ -0,0 +1,34 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+pub fn fixture(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let root = std::env::temp_dir().join(format!("ti84-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+
+    for (path, content) in files {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    root
+}
+
+pub fn compiler(root: &Path) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_compiler"));
+
+    command
+        .current_dir(root)
+        .env("ROOT_DIRECTORY", root)
+        .env("SOURCE_DATE_EPOCH", "315532800")
+        .env("SOURCE_REVISION", "test")
+        .env("AST_CACHE_DIR", "")
+        .env("FETCH_CACHE_DIR", "")
+        .env("LISTING_CACHE_TTL", "0")
+        .env_remove("OUTPUT_SINK")
+        .env_remove("WATERMARK_ID")
+        .env_remove("MAX_IMPORT_DEPTH");
+
+    command
+}
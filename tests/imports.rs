@@ -0,0 +1,148 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+use std::process::Output;
+use common::{compiler, fixture};
+
+fn run_compiler(root: &Path, args: &[&str]) -> Output {
+    compiler(root).args(args).output().unwrap()
+}
+
+fn bundle(root: &Path, script: &str, extra_args: &[&str]) -> (String, String) {
+    let output_directory = root.join("out");
+    let output = run_compiler(
+        root,
+        &[&["bundle", "-g", "games", "-s", script, "--unpacked", "--output", output_directory.to_str().unwrap()], extra_args].concat(),
+    );
+
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(output.status.success(), "{}", stderr);
+
+    (fs::read_to_string(output_directory.join(format!("{}.py", script))).unwrap(), stderr)
+}
+
+const HELPERS: &str = "def clamp(value, low, high):\n    return max(low, min(high, value))\n";
+
+#[test]
+fn project_imports_are_inlined_transitively() {
+    let root = fixture(
+        "imports-transitive",
+        &[
+            ("common/helpers.py", HELPERS),
+            ("games/vectors.py", "from common.helpers import clamp\n\ndef add(a, b):\n    return clamp(a + b, 0, 320)\n"),
+            ("games/physics.py", "from games.vectors import add\n\ndef step(x, speed):\n    return add(x, speed)\n"),
+            ("games/ball/download.py", "from games.physics import step\nprint(step(1, 2))\n"),
+        ],
+    );
+
+    let (bundle, _) = bundle(&root, "ball", &[]);
+
+    assert!(!bundle.contains("import"), "project imports should be inlined:\n{}", bundle);
+
+    let clamp = bundle.find("def clamp(").expect("clamp from common.helpers");
+    let add = bundle.find("def add(").expect("add from games/vectors.py");
+    let step = bundle.find("def step(").expect("step from games/physics.py");
+    let entry = bundle.find("print(step(1, 2))").expect("the entry script");
+    assert!(clamp < add && add < step && step < entry, "definitions should precede their users:\n{}", bundle);
+
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn circular_imports_are_reported_with_the_chain() {
+    let root = fixture(
+        "imports-cycle",
+        &[
+            ("common/helpers.py", HELPERS),
+            ("games/a.py", "from games.b import B\nA = 1\n"),
+            ("games/b.py", "from games.a import A\nB = 2\n"),
+            ("games/loop/download.py", "from games.a import A\nprint(A)\n"),
+        ],
+    );
+
+    let (bundle, stderr) = bundle(&root, "loop", &[]);
+
+    assert!(
+        stderr.contains("games/b.py:1: circular import games/a.py -> games/b.py -> games/a.py"),
+        "missing cycle warning: {}",
+        stderr
+    );
+    assert_eq!(bundle.matches("A = 1").count(), 1);
+    assert_eq!(bundle.matches("B = 2").count(), 1);
+
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn aliased_imports_bind_their_alias() {
+    let root = fixture(
+        "imports-alias",
+        &[
+            ("common/helpers.py", HELPERS),
+            ("games/physics.py", "def step(x, speed):\n    return x + speed\n"),
+            ("games/ball/download.py", "from games.physics import step as advance\nfrom common.helpers import clamp as limit\nprint(limit(advance(1, 2), 0, 2))\n"),
+        ],
+    );
+
+    let (bundle, _) = bundle(&root, "ball", &[]);
+
+    assert!(bundle.contains("def step(x, speed):"));
+    assert!(bundle.contains("advance = step"), "{}", bundle);
+    assert!(bundle.contains("def clamp(value, low, high):"));
+    assert!(bundle.contains("limit = clamp"), "{}", bundle);
+
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn import_depth_counts_only_modules_that_are_inlined() {
+    let root = fixture(
+        "imports-depth",
+        &[
+            ("common/helpers.py", HELPERS),
+            ("games/a.py", "A = 1\n"),
+            ("games/b.py", "from games.a import A\nB = A + 1\n"),
+            ("games/c.py", "from games.b import B\nC = B + 1\n"),
+            ("games/main/download.py", "from games.a import A\nfrom games.b import B\nprint(A, B)\n"),
+            ("games/deep/download.py", "from games.c import C\nprint(C)\n"),
+        ],
+    );
+
+    let (bundle, _) = bundle(&root, "main", &["--max-import-depth", "1"]);
+    assert_eq!(bundle, "A = 1\nB = A + 1\nprint(A, B)");
+
+    let output = run_compiler(&root, &["check", "-g", "games", "-s", "deep", "--max-import-depth", "1"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr).unwrap().contains("import chain games/deep/download.py -> games/c.py -> games/b.py is deeper than --max-import-depth 1"));
+
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn lints_in_a_shared_helper_are_grouped_across_scripts() {
+    let root = fixture(
+        "imports-grouped",
+        &[
+            ("common/helpers.py", "BIG = 4000000000\n\ndef banner():\n    print(\"a title that is far too wide for the calculator\")\n"),
+            ("games/a/download.py", "from common.helpers import BIG, banner\nbanner()\nprint(BIG)\n"),
+            ("games/b/download.py", "from common.helpers import BIG, banner\nx = 3000000000\nbanner()\n"),
+        ],
+    );
+
+    let output = run_compiler(&root, &["check", "-g", "games", "-s", "a,b", "--target-os", "5.6"]);
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(output.status.success(), "{}", stderr);
+
+    let warnings: Vec<&str> = stderr.lines().filter(|line| line.starts_with("warning: ")).collect();
+    let shared: Vec<&&str> = warnings.iter().filter(|warning| warning.starts_with("warning: common/helpers.py:")).collect();
+
+    assert_eq!(shared.len(), 2, "{}", stderr);
+    assert!(shared[0].starts_with("warning: common/helpers.py:1: integer 4000000000"), "{}", shared[0]);
+    assert!(shared[1].starts_with("warning: common/helpers.py:4: print() writes"), "{}", shared[1]);
+    assert!(shared.iter().all(|warning| warning.ends_with("(2 scripts: a, b)")), "{}", stderr);
+
+    assert!(warnings.iter().any(|warning| warning.starts_with("warning: games/b/download.py:2: integer 3000000000") && warning.ends_with("(b)")), "{}", stderr);
+
+    fs::remove_dir_all(root).unwrap();
+}
@@ -0,0 +1,35 @@
+mod common;
+
+use std::fs;
+use common::{compiler, fixture};
+
+#[test]
+fn pack_maps_script_names_onto_appvar_names() {
+    let root = fixture(
+        "pack-names",
+        &[
+            ("common/helpers.py", "def greet(name):\n    return \"hi \" + name\n"),
+            ("games/snake_game/download.py", "from common.helpers import greet\nprint(greet(\"snake\"))\n"),
+            ("games/pong/download.py", "print(\"pong\")\n"),
+        ],
+    );
+    let pack = root.join("pack");
+
+    let output = compiler(&root).args(["pack", "games", "--output", pack.to_str().unwrap()]).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let mut appvars: Vec<String> = fs::read_dir(pack.join("8xv")).unwrap().map(|entry| entry.unwrap().file_name().to_string_lossy().to_string()).collect();
+    appvars.sort();
+    assert_eq!(appvars, vec!["MENU.8xv", "PONG.8xv", "SNAKEGAM.8xv"]);
+
+    let menu = fs::read(pack.join("8xv/MENU.8xv")).unwrap();
+    assert!(String::from_utf8_lossy(&menu).contains("SCRIPTS = [\"PONG\", \"SNAKEGAM\"]"));
+
+    let readme = fs::read_to_string(pack.join("README.md")).unwrap();
+    assert!(readme.contains("| snake_game | SNAKEGAM |"), "{}", readme);
+
+    let metadata = fs::read_to_string(pack.join("pack.json")).unwrap();
+    assert!(metadata.contains("{\"name\":\"snake_game\",\"appvar\":\"SNAKEGAM\","), "{}", metadata);
+
+    fs::remove_dir_all(root).unwrap();
+}
@@ -0,0 +1,111 @@
+mod common;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Output;
+use common::{compiler, fixture};
+
+fn repository(name: &str) -> PathBuf {
+    fixture(
+        name,
+        &[
+            ("common/helpers.py", "def clamp_value(value, low, high):\n    return max(low, min(high, value))\n\ndef greet(name):\n    return \"hi \" + name\n\nSPEED = 2\n"),
+            ("games/physics.py", "from common.helpers import clamp\n\ndef step(x):\n    return clamp(x + 1, 0, 320)\n"),
+            ("games/pong/download.py", "from games.physics import step\nprint(step(1))\n"),
+            ("games/snake/download.py", "from common.helpers import greet\nprint(greet(\"snake\"))\n"),
+            ("tools/timer/download.py", "print(1)\n"),
+        ],
+    )
+}
+
+fn run(root: &Path, args: &[&str]) -> (Output, String) {
+    let output = compiler(root).args(args).output().unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+    (output, stderr)
+}
+
+#[test]
+fn check_helpers_reports_scripts_importing_removed_helpers() {
+    let root = repository("repository-check-helpers");
+
+    let (output, stderr) = run(&root, &["check-helpers"]);
+    assert!(!output.status.success());
+    assert!(stderr.contains("common/helpers.py does not define clamp, which 1 script import(s) use; did you mean clamp_value?"), "{}", stderr);
+    assert!(stderr.contains("  games/pong (imported in games/physics.py)"), "{}", stderr);
+    assert!(stderr.contains("broken: 1 of 3 scripts"), "{}", stderr);
+    assert!(!stderr.contains("games/snake"), "{}", stderr);
+
+    fs::write(root.join("games/physics.py"), "from common.helpers import clamp_value\n\ndef step(x):\n    return clamp_value(x + 1, 0, 320)\n").unwrap();
+
+    let (output, stderr) = run(&root, &["check-helpers"]);
+    assert!(output.status.success(), "{}", stderr);
+    assert!(stderr.contains("ok: all 3 scripts resolve against common/helpers.py"), "{}", stderr);
+
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn who_uses_lists_each_importing_script_with_the_import_line() {
+    let root = repository("repository-who-uses");
+    fs::write(root.join("games/snake/download.py"), "import ti_system\nfrom common.helpers import greet, SPEED\nprint(greet(\"snake\"), SPEED)\n").unwrap();
+
+    let (output, stderr) = run(&root, &["who-uses", "greet"]);
+    assert!(output.status.success(), "{}", stderr);
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "games/snake: games/snake/download.py:2\n");
+
+    let (output, _) = run(&root, &["who-uses", "clamp"]);
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "games/pong: games/physics.py:1\n");
+
+    let (output, _) = run(&root, &["who-uses", "step", "--module", "games.physics"]);
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "games/pong: games/pong/download.py:1\n");
+
+    let (output, stderr) = run(&root, &["who-uses", "unused"]);
+    assert!(output.status.success() && output.stdout.is_empty());
+    assert!(stderr.contains("no script imports unused from common.helpers"), "{}", stderr);
+
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn dead_helpers_lists_what_no_bundle_inlines() {
+    let root = repository("repository-dead-helpers");
+    fs::write(
+        root.join("common/helpers.py"),
+        "LIMIT = 320\n\ndef clamp(value, low, high):\n    return max(low, min(high, value))\n\ndef edge(x):\n    return clamp(x, 0, LIMIT)\n\ndef greet(name):\n    return \"hi \" + name\n\ndef unused():\n    pass\n",
+    )
+    .unwrap();
+    fs::write(root.join("games/physics.py"), "from common.helpers import edge\n\ndef step(x):\n    return edge(x + 1)\n").unwrap();
+
+    let (output, stderr) = run(&root, &["dead-helpers"]);
+    assert!(output.status.success(), "{}", stderr);
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "common/helpers.py:12: unused\n");
+    assert!(stderr.contains("1 of 5 helpers in common/helpers.py are not used by any script"), "{}", stderr);
+
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn check_changed_validates_only_the_scripts_that_include_a_changed_file() {
+    let root = repository("repository-check-changed");
+
+    let (output, stderr) = run(&root, &["check", "--changed", "games/physics.py"]);
+    assert!(output.status.success(), "{}", stderr);
+    assert!(stderr.contains("ok: pong.py") && !stderr.contains("snake") && !stderr.contains("timer"), "{}", stderr);
+
+    let (output, stderr) = run(&root, &["check", "--changed", root.join("common/helpers.py").to_str().unwrap(), "notes.txt"]);
+    assert!(output.status.success(), "{}", stderr);
+    assert!(stderr.contains("ok: pong.py") && stderr.contains("ok: snake.py") && !stderr.contains("timer"), "{}", stderr);
+
+    let (output, stderr) = run(&root, &["check", "--changed", "notes.txt"]);
+    assert!(output.status.success(), "{}", stderr);
+    assert!(stderr.contains("ok: no scripts include the changed files"), "{}", stderr);
+
+    fs::write(root.join("common/helpers.py"), "def greet(name:\n").unwrap();
+
+    let (output, stderr) = run(&root, &["check", "--changed", "common/helpers.py"]);
+    assert!(!output.status.success());
+    assert!(stderr.contains("failed: 2 of 2 scripts (pong, snake)"), "{}", stderr);
+    assert!(!stderr.contains("timer"), "{}", stderr);
+
+    fs::remove_dir_all(root).unwrap();
+}
@@ -0,0 +1,27 @@
+mod common;
+
+use std::fs;
+use common::{compiler, fixture};
+
+#[test]
+fn imports_through_a_symlink_out_of_the_project_are_refused() {
+    let root = fixture(
+        "sandbox-symlink",
+        &[
+            ("project/games/leak/download.py", "from common.secret import TOKEN\nprint(TOKEN)\n"),
+            ("outside/secret.py", "TOKEN = \"hunter2\"\n"),
+        ],
+    );
+    let project = root.join("project");
+    fs::create_dir_all(project.join("common")).unwrap();
+    std::os::unix::fs::symlink(root.join("outside/secret.py"), project.join("common/secret.py")).unwrap();
+
+    let output = compiler(&project).args(["bundle", "-g", "games", "-s", "leak", "--dev"]).output().unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+
+    assert!(!output.status.success());
+    assert!(stderr.contains("resolves outside ROOT_DIRECTORY"), "{}", stderr);
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("hunter2"));
+
+    fs::remove_dir_all(root).unwrap();
+}
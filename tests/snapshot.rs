@@ -0,0 +1,52 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+use std::process::Output;
+use common::{compiler, fixture};
+
+fn snapshot(root: &Path, extra_args: &[&str]) -> (Output, String) {
+    let output = compiler(root).arg("snapshot").args(extra_args).output().unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+    (output, stderr)
+}
+
+#[test]
+fn snapshots_catch_helper_changes_until_updated() {
+    let root = fixture(
+        "snapshot",
+        &[
+            ("common/helpers.py", "def greet(name):\n    return \"hi \" + name\n"),
+            ("games/demo/download.py", "from common.helpers import greet\nprint(greet(\"demo\"))\n"),
+            ("tools/timer/download.py", "print(1)\n"),
+        ],
+    );
+
+    let (output, stderr) = snapshot(&root, &[]);
+    assert!(!output.status.success());
+    assert!(stderr.contains("new: snapshots/games/demo.py has no snapshot"), "{}", stderr);
+
+    let (output, stderr) = snapshot(&root, &["--update"]);
+    assert!(output.status.success(), "{}", stderr);
+    assert!(root.join("snapshots/games/demo.py").exists() && root.join("snapshots/tools/timer.py").exists(), "{}", stderr);
+
+    let (output, stderr) = snapshot(&root, &[]);
+    assert!(output.status.success(), "{}", stderr);
+    assert!(stderr.contains("ok: 2 snapshot(s) match"), "{}", stderr);
+
+    fs::write(root.join("common/helpers.py"), "def greet(name):\n    return \"hello \" + name\n").unwrap();
+    fs::write(root.join("snapshots/games/gone.py"), "print(0)").unwrap();
+
+    let (output, stderr) = snapshot(&root, &[]);
+    assert!(!output.status.success());
+    assert!(stderr.contains("changed: snapshots/games/demo.py:"), "{}", stderr);
+    assert!(stderr.contains("+     return \"hello \" + name"), "{}", stderr);
+    assert!(stderr.contains("stale: snapshots/games/gone.py is no longer built"), "{}", stderr);
+
+    let (output, stderr) = snapshot(&root, &["--update"]);
+    assert!(output.status.success(), "{}", stderr);
+    assert!(!root.join("snapshots/games/gone.py").exists());
+    assert!(snapshot(&root, &[]).0.status.success());
+
+    fs::remove_dir_all(root).unwrap();
+}
@@ -0,0 +1,70 @@
+mod common;
+
+use std::fs;
+use std::io::{Cursor, Read};
+use std::path::Path;
+use std::process::Output;
+use base64::{Engine as _, engine::general_purpose};
+use common::{compiler, fixture};
+
+const FILES: &[(&str, &str)] = &[
+    ("common/helpers.py", "def greet(name):\n    return \"hi \" + name\n"),
+    ("games/demo/download.py", "from common.helpers import greet\nprint(greet(\"demo\"))\n"),
+];
+
+fn run_bundle(root: &Path, extra_args: &[&str]) -> Output {
+    compiler(root).args(["bundle", "-g", "games", "-s", "demo"]).args(extra_args).output().unwrap()
+}
+
+#[test]
+fn stdout_carries_only_the_zip() {
+    let root = fixture("streams-zip", FILES);
+    let output = run_bundle(&root, &["--dev"]);
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let zip_content = general_purpose::STANDARD.decode(stdout.trim()).expect("stdout is not a single base64 payload");
+
+    let mut archive = zip::ZipArchive::new(Cursor::new(zip_content)).unwrap();
+    let mut bundle = String::new();
+    archive.by_name("demo.py").unwrap().read_to_string(&mut bundle).unwrap();
+    assert!(bundle.contains("def greet(name):"));
+
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("print(greet(\"demo\"))"), "the --dev preview should go to stderr: {}", stderr);
+
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn dry_run_reports_on_stderr_only() {
+    let root = fixture("streams-dry-run", FILES);
+    let output = run_bundle(&root, &["--dry-run"]);
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(output.stdout.is_empty(), "--dry-run wrote to stdout: {}", String::from_utf8_lossy(&output.stdout));
+    assert!(String::from_utf8(output.stderr).unwrap().contains("would emit demo.py"));
+
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn clipboard_sink_copies_the_zip_instead_of_printing_it() {
+    let root = fixture("streams-clipboard", FILES);
+    let copied = root.join("clipboard.txt");
+    let output = compiler(&root)
+        .args(["bundle", "-g", "games", "-s", "demo", "--sink", "clipboard"])
+        .env("CLIPBOARD_COMMAND", format!("tee {}", copied.display()))
+        .output()
+        .unwrap();
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(output.stdout.is_empty(), "the clipboard sink wrote to stdout: {}", String::from_utf8_lossy(&output.stdout));
+
+    let zip_content = general_purpose::STANDARD.decode(fs::read_to_string(&copied).unwrap()).expect("the clipboard should hold one base64 payload");
+    let mut archive = zip::ZipArchive::new(Cursor::new(zip_content)).unwrap();
+    assert!(archive.by_name("demo.py").is_ok());
+
+    fs::remove_dir_all(root).unwrap();
+}
@@ -0,0 +1,70 @@
+mod common;
+
+use std::fs;
+use std::io::Cursor;
+use base64::{Engine as _, engine::general_purpose};
+use common::{compiler, fixture};
+
+const FILES: &[(&str, &str)] = &[
+    ("common/helpers.py", "X = 1\n"),
+    ("games/demo/download.py", "print(1)\n"),
+];
+
+#[test]
+fn a_missing_clipboard_tool_falls_back_to_printing_the_zip() {
+    let root = fixture("tools-clipboard", FILES);
+    let output = compiler(&root)
+        .args(["bundle", "-g", "games", "-s", "demo", "--sink", "clipboard"])
+        .env("CLIPBOARD_COMMAND", "no-such-clipboard-tool")
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+
+    assert!(output.status.success(), "{}", stderr);
+    assert!(stderr.contains("warning: no-such-clipboard-tool not found: the clipboard sink will print"), "{}", stderr);
+
+    let zip_content = general_purpose::STANDARD.decode(String::from_utf8(output.stdout).unwrap().trim()).unwrap();
+    assert!(zip::ZipArchive::new(Cursor::new(zip_content)).unwrap().by_name("demo.py").is_ok());
+
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn transfer_without_ti_connect_still_writes_the_appvars() {
+    let root = fixture("tools-transfer", FILES);
+    let folder = root.join("appvars");
+    let output = compiler(&root)
+        .args(["transfer", "-g", "games", "-s", "demo", "--folder", folder.to_str().unwrap()])
+        .env("TI_CONNECT_PATH", root.join("missing/TI Connect CE"))
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+
+    assert!(output.status.success(), "{}", stderr);
+    assert!(stderr.contains("warning: TI Connect CE not found"), "{}", stderr);
+    assert!(folder.join("demo.8xv").exists(), "{}", stderr);
+
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn bundles_without_git_record_an_unknown_revision() {
+    let root = fixture("tools-git", FILES);
+    let archive = root.join("out.zip");
+    let output = compiler(&root)
+        .args(["bundle", "-g", "games", "-s", "demo", "--output", archive.to_str().unwrap()])
+        .env_remove("SOURCE_REVISION")
+        .env("PATH", "")
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+
+    assert!(output.status.success(), "{}", stderr);
+    assert!(stderr.contains("warning: git not found"), "{}", stderr);
+
+    let mut zip = zip::ZipArchive::new(fs::File::open(&archive).unwrap()).unwrap();
+    let comment = zip.by_name("demo.py").unwrap().comment().to_string();
+    assert!(comment.contains("source unknown"), "{}", comment);
+
+    fs::remove_dir_all(root).unwrap();
+}